@@ -1,8 +1,9 @@
 use super::{validate_file, CmdExecutor};
-use crate::{process_decode, process_encode};
-use clap::{Args, Subcommand, ValueEnum};
+use crate::{check_stdin_input, process_decode, process_encode, CmdContext, CmdOutput};
+use clap::{Args, Subcommand, ValueEnum, ValueHint};
 use enum_dispatch::enum_dispatch;
-use std::{fmt, fs, path::Path};
+use serde_json::json;
+use std::fmt;
 
 #[derive(Debug, Subcommand)]
 #[enum_dispatch(CmdExecutor)]
@@ -19,7 +20,7 @@ pub enum Base64Command {
 #[derive(Debug, Args)]
 pub struct Base64EncodeOpts {
     /// input from stdin or file to encode
-    #[arg(short, long, value_parser = validate_file, default_value = "-")]
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
     pub input: String,
 
     /// base64 format, like: standard or url (default: standard)
@@ -30,7 +31,7 @@ pub struct Base64EncodeOpts {
 #[derive(Debug, Args)]
 pub struct Base64DecodeOpts {
     /// input from stdin or file to decode
-    #[arg(short, long, value_parser = validate_file, default_value = "-")]
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
     pub input: String,
 
     /// base64 format, like: standard or url (default: standard)
@@ -48,28 +49,26 @@ pub enum AlphabetRange {
 }
 
 impl CmdExecutor for Base64EncodeOpts {
-    async fn execute(self) -> anyhow::Result<()> {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        check_stdin_input(&self.input, "--input <file>")?;
         let encoded = process_encode(&self.input, &self.format.to_string())?;
-        println!("{}", encoded);
-        Ok(())
+        Ok(CmdOutput::Json {
+            json: json!({ "command": "base64 encode", "result": encoded }),
+            lines: vec![encoded],
+        })
     }
 }
 
 impl CmdExecutor for Base64DecodeOpts {
-    async fn execute(self) -> anyhow::Result<()> {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
         let decoded = process_decode(&self.input, &self.format.to_string())?;
-        match String::from_utf8(decoded.clone()) {
-            Ok(result) => println!("{}", result),
-            Err(_) => {
-                let file = Path::new("base64_decode.output");
-                fs::write(file, decoded)?;
-                println!(
-                    "The decode data is not a string, please check the file {}",
-                    file.display()
-                )
-            }
+        match String::from_utf8(decoded) {
+            Ok(result) => Ok(CmdOutput::Json {
+                json: json!({ "command": "base64 decode", "result": result }),
+                lines: vec![result],
+            }),
+            Err(e) => Ok(CmdOutput::Bytes(e.into_bytes())),
         }
-        Ok(())
     }
 }
 
@@ -81,3 +80,59 @@ impl fmt::Display for AlphabetRange {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::run_with_output;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_encode_to_output_file() {
+        let (output, result) =
+            run_with_output(&["base64", "encode", "-i", "assets/decode.b64"]).await;
+        result.unwrap();
+        assert_eq!(output, "VGhpcyBpcyBhIGJhc2U2NCBlbmNvZGluZyB0ZXh0Lgo=\n");
+    }
+
+    #[tokio::test]
+    async fn test_decode_binary_to_output_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input.b64");
+        std::fs::write(&input, "AP8Q").unwrap();
+        let args = ["base64", "decode", "-i", input.to_str().unwrap()];
+        let (output, result) = run_with_output(&args).await;
+        result.unwrap();
+        // Written as is, 0xff isn't valid UTF-8.
+        assert_eq!(output, "\0\u{fffd}\x10");
+
+        let args = ["--json", "base64", "decode", "-i", input.to_str().unwrap()];
+        let (_, result) = run_with_output(&args).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_json_output() {
+        let args = ["--json", "base64", "encode", "-i", "assets/decode.b64"];
+        let (output, result) = run_with_output(&args).await;
+        result.unwrap();
+        let encoded = serde_json::from_str::<Value>(&output).unwrap();
+        assert_eq!(
+            encoded,
+            json!({
+                "command": "base64 encode",
+                "result": "VGhpcyBpcyBhIGJhc2U2NCBlbmNvZGluZyB0ZXh0Lgo=",
+            })
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input.b64");
+        std::fs::write(&input, encoded["result"].as_str().unwrap()).unwrap();
+        let args = ["--json", "base64", "decode", "-i", input.to_str().unwrap()];
+        let (output, result) = run_with_output(&args).await;
+        result.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap(),
+            json!({ "command": "base64 decode", "result": "This is a base64 encoding text.\n" })
+        );
+    }
+}
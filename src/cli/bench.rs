@@ -0,0 +1,131 @@
+use super::{validate_size, CmdExecutor};
+use crate::{process_bench, CmdContext, CmdOutput};
+use clap::{Args, ValueEnum};
+use serde_json::json;
+use std::{fmt, time::Duration};
+
+#[derive(Debug, Args)]
+pub struct BenchOpts {
+    /// the operation to time
+    #[arg(value_enum)]
+    pub op: BenchOp,
+
+    /// how many times to run it
+    #[arg(short = 'n', long, default_value_t = 1000)]
+    pub iterations: u32,
+
+    /// the input size, like: 512, 64KB, 1MB
+    #[arg(short, long, value_parser = validate_size, default_value = "1KB")]
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum BenchOp {
+    /// base64 encode a file
+    Base64Encode,
+
+    /// base64 decode a file
+    Base64Decode,
+
+    /// hex encode a file
+    HexEncode,
+
+    /// encrypt with chacha20poly1305
+    #[cfg(feature = "text")]
+    Encrypt,
+
+    /// sha256 digest of a file
+    Sha256,
+
+    /// blake3 digest of a file
+    Blake3,
+}
+
+impl CmdExecutor for BenchOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let op = self.op.to_string();
+        let report = process_bench(&op, self.iterations, self.size)?;
+        let percentiles = [("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("max", 1.0)];
+        let latency_secs = percentiles
+            .iter()
+            .map(|(name, p)| (name.to_string(), report.percentile(*p).as_secs_f64().into()))
+            .collect::<serde_json::Map<_, _>>();
+        let latency = percentiles
+            .iter()
+            .map(|(name, p)| format!("{} {}", name, format_latency(report.percentile(*p))))
+            .collect::<Vec<_>>();
+        Ok(CmdOutput::Json {
+            json: json!({
+                "command": "bench",
+                "op": op,
+                "iterations": report.iterations,
+                "size": report.size,
+                "total_secs": report.total.as_secs_f64(),
+                "ops_per_sec": report.ops_per_sec(),
+                "bytes_per_sec": report.bytes_per_sec(),
+                "latency_secs": latency_secs,
+            }),
+            lines: vec![
+                format!(
+                    "{}: {} runs of {} bytes in {}",
+                    op,
+                    report.iterations,
+                    report.size,
+                    format_latency(report.total)
+                ),
+                format!("ops/sec: {:.1}", report.ops_per_sec()),
+                format!(
+                    "throughput: {:.2} MiB/s",
+                    report.bytes_per_sec() / (1024.0 * 1024.0)
+                ),
+                format!("latency: {}", latency.join(", ")),
+            ],
+        })
+    }
+}
+
+fn format_latency(d: Duration) -> String {
+    match d.as_nanos() {
+        n if n < 1_000 => format!("{}ns", n),
+        n if n < 1_000_000 => format!("{:.1}µs", n as f64 / 1e3),
+        n if n < 1_000_000_000 => format!("{:.2}ms", n as f64 / 1e6),
+        n => format!("{:.2}s", n as f64 / 1e9),
+    }
+}
+
+impl fmt::Display for BenchOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BenchOp::Base64Encode => write!(f, "base64-encode"),
+            BenchOp::Base64Decode => write!(f, "base64-decode"),
+            BenchOp::HexEncode => write!(f, "hex-encode"),
+            #[cfg(feature = "text")]
+            BenchOp::Encrypt => write!(f, "encrypt"),
+            BenchOp::Sha256 => write!(f, "sha256"),
+            BenchOp::Blake3 => write!(f, "blake3"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_with_output;
+
+    #[test]
+    fn test_format_latency() {
+        assert_eq!(format_latency(Duration::from_nanos(420)), "420ns");
+        assert_eq!(format_latency(Duration::from_micros(1500)), "1.50ms");
+        assert_eq!(format_latency(Duration::from_secs(2)), "2.00s");
+    }
+
+    #[tokio::test]
+    async fn test_bench_json_output() {
+        let args = ["--json", "bench", "base64-encode", "-n", "10", "-s", "16"];
+        let (output, result) = run_with_output(&args).await;
+        result.unwrap();
+        let value = serde_json::from_str::<serde_json::Value>(&output).unwrap();
+        assert_eq!(value["op"], "base64-encode");
+        assert!(value["ops_per_sec"].as_f64().unwrap() > 0.0);
+    }
+}
@@ -0,0 +1,17 @@
+use super::CmdExecutor;
+use crate::{from_clipboard, CmdContext, CmdOutput};
+use clap::Args;
+use serde_json::json;
+
+#[derive(Debug, Args)]
+pub struct ClipboardOpts {}
+
+impl CmdExecutor for ClipboardOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let text = from_clipboard()?;
+        Ok(CmdOutput::Json {
+            json: json!({ "command": "clipboard", "text": text }),
+            lines: vec![text],
+        })
+    }
+}
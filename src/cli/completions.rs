@@ -0,0 +1,76 @@
+use super::{Cli, CmdExecutor};
+use crate::{CmdContext, CmdOutput};
+use clap::{Args, CommandFactory};
+use clap_complete::{generate, CompleteEnv, Shell};
+use std::io::Write;
+
+#[derive(Debug, Args)]
+pub struct CompletionsOpts {
+    /// the shell to generate the script for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+impl CmdExecutor for CompletionsOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let mut script = Vec::new();
+        render_completions(self.shell, &mut script);
+        Ok(CmdOutput::Bytes(script))
+    }
+}
+
+/// Answer a dynamic completion request when the shell invoked us with
+/// `COMPLETE=<shell>`, e.g. `source <(COMPLETE=bash rcli)`. Exits the process
+/// in that case and returns normally otherwise.
+pub fn complete_dynamic() {
+    CompleteEnv::with_factory(Cli::command).complete();
+}
+
+fn render_completions(shell: Shell, buf: &mut dyn Write) {
+    let mut cmd = Cli::command();
+    let name = cmd.get_name().to_string();
+    generate(shell, &mut cmd, name, buf);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_completions() {
+        let mut buf = Vec::new();
+        render_completions(Shell::Bash, &mut buf);
+        let script = String::from_utf8(buf).unwrap();
+        assert!(!script.is_empty());
+        assert!(script.contains("completions"));
+    }
+
+    #[cfg(feature = "csv")]
+    fn candidates(args: &[&str], dir: Option<&std::path::Path>) -> Vec<String> {
+        let mut cmd = Cli::command();
+        let args = args.iter().map(Into::into).collect::<Vec<_>>();
+        let index = args.len() - 1;
+        clap_complete::engine::complete(&mut cmd, args, index, dir)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.get_value().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_dynamic_format_candidates() {
+        let values = candidates(&["rcli", "csv", "--format", ""], None);
+        assert!(values.contains(&"json".to_string()));
+        assert!(values.contains(&"yaml".to_string()));
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_dynamic_file_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("juice.csv"), "a,b\n").unwrap();
+        let values = candidates(&["rcli", "csv", "-i", "ju"], Some(dir.path()));
+        assert_eq!(values, vec!["juice.csv"]);
+    }
+}
@@ -0,0 +1,254 @@
+use super::{validate_file, CmdExecutor};
+use crate::{
+    compression_from_extension, detect_compression, get_reader, get_writer, process_compress,
+    process_decompress, CliError, CmdContext, CmdOutput, MAGIC_LEN,
+};
+use anyhow::Result;
+use clap::{Args, ValueEnum, ValueHint};
+use std::{
+    fmt,
+    io::{Read, Write},
+};
+
+#[derive(Debug, Args)]
+pub struct CompressOpts {
+    /// the compression algorithm
+    #[arg(short, long, value_enum, default_value = "gzip")]
+    pub algo: CompressAlgo,
+
+    /// the compression level, default: 6 for gzip and xz, 3 for zstd
+    #[arg(short, long)]
+    pub level: Option<u32>,
+
+    /// input from stdin or file to compress
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
+    pub input: String,
+
+    /// the compressed file, default: stdout or --output
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct DecompressOpts {
+    /// the compression algorithm, default: from the input's magic bytes or extension
+    #[arg(short, long, value_enum)]
+    pub algo: Option<CompressAlgo>,
+
+    /// input from stdin or file to decompress
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
+    pub input: String,
+
+    /// the decompressed file, default: stdout or --output
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, ValueEnum, Clone, Copy)]
+pub enum CompressAlgo {
+    /// gzip, levels 0 to 9
+    Gzip,
+
+    /// zstandard, levels 1 to 22
+    Zstd,
+
+    /// xz, levels 0 to 9
+    Xz,
+}
+
+impl CmdExecutor for CompressOpts {
+    async fn execute(self, ctx: &mut CmdContext) -> Result<CmdOutput> {
+        let mut reader = get_reader(&self.input)?;
+        write_output(self.output.as_deref(), ctx, |output| {
+            process_compress(&mut reader, output, &self.algo.to_string(), self.level)?;
+            Ok(())
+        })
+    }
+}
+
+impl CmdExecutor for DecompressOpts {
+    async fn execute(self, ctx: &mut CmdContext) -> Result<CmdOutput> {
+        let mut reader = get_reader(&self.input)?;
+        let mut header = Vec::new();
+        (&mut reader)
+            .take(MAGIC_LEN as u64)
+            .read_to_end(&mut header)?;
+        let algo = match self.algo {
+            Some(algo) => algo.to_string(),
+            None => detect_compression(&header)
+                .or_else(|| compression_from_extension(&self.input))
+                .ok_or_else(|| {
+                    CliError::Usage(format!(
+                        "can't tell how {} is compressed, pass --algo",
+                        self.input
+                    ))
+                })?
+                .to_string(),
+        };
+        write_output(self.output.as_deref(), ctx, |output| {
+            process_decompress(&mut header.as_slice().chain(reader), output, &algo)?;
+            Ok(())
+        })
+    }
+}
+
+// Write to the -o file, or return the data as the command's output. A dry run
+// only counts the bytes the file would get.
+fn write_output(
+    output: Option<&str>,
+    ctx: &mut CmdContext,
+    write: impl FnOnce(&mut dyn Write) -> Result<()>,
+) -> Result<CmdOutput> {
+    match output {
+        Some(path) if path != "-" && ctx.is_dry_run() => {
+            let mut counter = ByteCounter(0);
+            write(&mut counter)?;
+            ctx.skip(path, counter.0);
+        }
+        Some(path) if path != "-" => {
+            let mut file = get_writer(path)?;
+            write(&mut file)?;
+            file.flush()?;
+        }
+        _ => {
+            let mut data = Vec::new();
+            write(&mut data)?;
+            return Ok(CmdOutput::Bytes(data));
+        }
+    }
+    Ok(CmdOutput::None)
+}
+
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Display for CompressAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressAlgo::Gzip => write!(f, "gzip"),
+            CompressAlgo::Zstd => write!(f, "zstd"),
+            CompressAlgo::Xz => write!(f, "xz"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cli::{run_with_output, Cli},
+        CmdContext, CmdExecutor, CmdOutput,
+    };
+    use clap::Parser;
+
+    #[tokio::test]
+    async fn test_compress_decompress_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for (algo, name) in [
+            ("gzip", "a.csv.gz"),
+            ("zstd", "a.csv.zst"),
+            ("xz", "a.csv.xz"),
+        ] {
+            let compressed = dir.path().join(name);
+            let compressed = compressed.to_str().unwrap();
+            let args = [
+                "compress",
+                "-a",
+                algo,
+                "-i",
+                "assets/juventus.csv",
+                "-o",
+                compressed,
+            ];
+            run_with_output(&args).await.1.unwrap();
+
+            let (output, result) = run_with_output(&["decompress", "-i", compressed]).await;
+            result.unwrap();
+            assert_eq!(
+                output,
+                std::fs::read_to_string("assets/juventus.csv").unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decompress_by_magic_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        for algo in ["gzip", "zstd", "xz"] {
+            // A name that says nothing, as downloads often have.
+            let compressed = dir.path().join(format!("download-{}", algo));
+            let compressed = compressed.to_str().unwrap();
+            let args = [
+                "compress",
+                "-a",
+                algo,
+                "-i",
+                "assets/juventus.csv",
+                "-o",
+                compressed,
+            ];
+            run_with_output(&args).await.1.unwrap();
+
+            let (output, result) = run_with_output(&["decompress", "-i", compressed]).await;
+            result.unwrap();
+            assert_eq!(
+                output,
+                std::fs::read_to_string("assets/juventus.csv").unwrap()
+            );
+        }
+
+        let (_, result) = run_with_output(&["decompress", "-i", "assets/juventus.csv"]).await;
+        assert_eq!(crate::exit_code(&result.unwrap_err()), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let compressed = dir.path().join("juventus.csv.gz");
+        let compressed = compressed.to_str().unwrap();
+        let args = ["compress", "-i", "assets/juventus.csv", "-o", compressed];
+        run_with_output(&args).await.1.unwrap();
+
+        let target = dir.path().join("out");
+        let target = target.to_str().unwrap();
+        for args in [
+            [
+                "rcli",
+                "--dry-run",
+                "compress",
+                "-i",
+                "assets/juventus.csv",
+                "-o",
+                target,
+            ],
+            [
+                "rcli",
+                "--dry-run",
+                "decompress",
+                "-i",
+                compressed,
+                "-o",
+                target,
+            ],
+        ] {
+            let cli = Cli::try_parse_from(args).unwrap();
+            let mut ctx = CmdContext::default().with_dry_run(cli.dry_run);
+            let result = cli.cmd.execute(&mut ctx).await.unwrap();
+            assert!(matches!(result, CmdOutput::None));
+            let skipped = ctx.take_skipped();
+            assert_eq!(skipped.len(), 1);
+            assert_eq!(skipped[0].0, std::path::Path::new(target));
+            assert!(skipped[0].1 > 0);
+            assert!(!std::path::Path::new(target).exists());
+        }
+    }
+}
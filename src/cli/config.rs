@@ -0,0 +1,140 @@
+use crate::CliError;
+use anyhow::Result;
+use clap::Command;
+use serde::Deserialize;
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+// Defaults for command options, like:
+//
+// output_dir = "/tmp/rcli"
+//
+// [http]
+// port = 3000
+//
+// [genpass]
+// length = 24
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub output_dir: Option<String>,
+    pub http: HttpDefaults,
+    pub genpass: GenPassDefaults,
+    pub csv: CsvDefaults,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpDefaults {
+    pub addr: Option<String>,
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenPassDefaults {
+    pub length: Option<u8>,
+    pub lower: Option<bool>,
+    pub upper: Option<bool>,
+    pub digits: Option<bool>,
+    pub symbol: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CsvDefaults {
+    pub format: Option<String>,
+}
+
+impl Config {
+    // Read the given file, or the default one if it exists.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let content = fs::read_to_string(&path).map_err(|e| {
+            CliError::Usage(format!("Failed to read config {}: {}", path.display(), e))
+        })?;
+        let config = toml::from_str(&content)
+            .map_err(|e| CliError::Usage(format!("Invalid config {}: {}", path.display(), e)))?;
+        Ok(config)
+    }
+
+    // Replace the defaults of the options, so flags on the command line still win.
+    pub fn apply(&self, cmd: Command) -> Command {
+        let http = &self.http;
+        let genpass = &self.genpass;
+        let defaults = [
+            (&[][..], "output_dir", self.output_dir.clone()),
+            (&["http", "serve"], "addr", http.addr.clone()),
+            (&["http", "serve"], "port", http.port.map(|v| v.to_string())),
+            (&["serve"], "addr", http.addr.clone()),
+            (&["serve"], "port", http.port.map(|v| v.to_string())),
+            (
+                &["genpass"],
+                "length",
+                genpass.length.map(|v| v.to_string()),
+            ),
+            (&["genpass"], "lower", genpass.lower.map(|v| v.to_string())),
+            (&["genpass"], "upper", genpass.upper.map(|v| v.to_string())),
+            (
+                &["genpass"],
+                "digits",
+                genpass.digits.map(|v| v.to_string()),
+            ),
+            (
+                &["genpass"],
+                "symbol",
+                genpass.symbol.map(|v| v.to_string()),
+            ),
+            (&["csv"], "format", self.csv.format.clone()),
+        ];
+        defaults
+            .into_iter()
+            .fold(cmd, |cmd, (path, arg, value)| match value {
+                Some(value) => set_default(cmd, path, arg, value),
+                None => cmd,
+            })
+    }
+}
+
+fn set_default(cmd: Command, path: &[&str], arg: &str, value: String) -> Command {
+    match path.split_first() {
+        // The subcommand may be left out of this build by its cargo feature.
+        Some((name, _)) if cmd.find_subcommand(name).is_none() => cmd,
+        Some((name, rest)) => cmd.mut_subcommand(*name, |sub| set_default(sub, rest, arg, value)),
+        None => cmd.mut_arg(arg, |a| a.default_value(value)),
+    }
+}
+
+// ~/.config/rcli/config.toml, or under $XDG_CONFIG_HOME if set.
+fn default_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(dir.join("rcli").join("config.toml"))
+}
+
+// The config file has to be known before the args can be parsed with its defaults.
+pub(super) fn config_arg(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
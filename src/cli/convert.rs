@@ -0,0 +1,55 @@
+use super::{validate_file, CmdExecutor};
+use crate::{data_format_from_extension, process_convert, CliError, CmdContext, CmdOutput};
+use anyhow::Result;
+use clap::{Args, ValueEnum, ValueHint};
+use std::fmt;
+
+#[derive(Debug, Args)]
+pub struct ConvertOpts {
+    /// input from stdin or file to convert
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
+    pub input: String,
+
+    /// the input format, default: from the input's extension
+    #[arg(long, value_enum)]
+    pub from: Option<DataFormat>,
+
+    /// the output format
+    #[arg(long, value_enum)]
+    pub to: DataFormat,
+}
+
+#[derive(Debug, ValueEnum, Clone, Copy)]
+pub enum DataFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl CmdExecutor for ConvertOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> Result<CmdOutput> {
+        let from = match self.from {
+            Some(from) => from.to_string(),
+            None => data_format_from_extension(&self.input)
+                .ok_or_else(|| {
+                    CliError::Usage(format!(
+                        "can't tell the format of {}, pass --from",
+                        self.input
+                    ))
+                })?
+                .to_string(),
+        };
+        let converted = process_convert(&self.input, &from, &self.to.to_string())?;
+        Ok(CmdOutput::Text(converted.trim_end().to_string()))
+    }
+}
+
+impl fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataFormat::Json => write!(f, "json"),
+            DataFormat::Yaml => write!(f, "yaml"),
+            DataFormat::Toml => write!(f, "toml"),
+        }
+    }
+}
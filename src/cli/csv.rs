@@ -1,12 +1,16 @@
 use super::{validate_file, CmdExecutor};
-use crate::process_csv;
-use clap::{Args, ValueEnum};
-use std::fmt;
+use crate::{
+    is_url, process_csv_convert, render_error, should_color, watch_file, CliError, CmdContext,
+    CmdOutput,
+};
+use chrono::Local;
+use clap::{Args, ValueEnum, ValueHint};
+use std::{fmt, path::Path};
 
 #[derive(Debug, Args)]
 pub struct CsvOpts {
     /// Input CSV file path
-    #[arg(short, long, value_parser = validate_file)]
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath)]
     pub input: String,
 
     /// Output file path
@@ -24,6 +28,10 @@ pub struct CsvOpts {
     /// Whether to include header in output
     #[arg(long, default_value_t = true)]
     pub header: bool,
+
+    /// Convert again each time the input file changes
+    #[arg(short, long)]
+    pub watch: bool,
 }
 
 #[derive(Debug, ValueEnum, Clone, Copy)]
@@ -36,13 +44,41 @@ pub enum OutputFormat {
 }
 
 impl CmdExecutor for CsvOpts {
-    async fn execute(self) -> anyhow::Result<()> {
+    async fn execute(self, ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
         let output = if let Some(output) = self.output {
             output
         } else {
-            format!("output.{}", self.format)
+            let path = ctx.output_path(format!("output.{}", self.format))?;
+            path.to_string_lossy().into_owned()
+        };
+        let format = self.format.to_string();
+        let convert = |ctx: &mut CmdContext| {
+            let content = process_csv_convert(&self.input, &format)?;
+            ctx.write_file(&output, content)
         };
-        process_csv(&self.input, &output, &self.format.to_string())
+        if !self.watch {
+            convert(ctx)?;
+            return Ok(CmdOutput::None);
+        }
+        if self.input == "-" || is_url(&self.input) {
+            return Err(CliError::Usage("--watch needs a local input file".to_string()).into());
+        }
+        let mut runs = 0;
+        watch_file(Path::new(&self.input), || {
+            if runs > 0 {
+                eprintln!("--- {} ---", Local::now().format("%Y-%m-%d %H:%M:%S"));
+            }
+            runs += 1;
+            // Keep watching, the next save may fix a half edited file.
+            if let Err(e) = convert(ctx) {
+                eprintln!("{}", render_error(&e, should_color(&std::io::stderr())));
+            }
+            for (path, len) in ctx.take_skipped() {
+                eprintln!("Would write {} bytes to {}", len, path.display());
+            }
+            true
+        })?;
+        Ok(CmdOutput::None)
     }
 }
 
@@ -54,3 +90,38 @@ impl fmt::Display for OutputFormat {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cli::Cli, Output};
+    use clap::Parser;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_dry_run_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("players.json");
+        let report = dir.path().join("report");
+        let args = [
+            "rcli",
+            "--dry-run",
+            "csv",
+            "-i",
+            "assets/juventus.csv",
+            "-o",
+            target.to_str().unwrap(),
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(cli.dry_run);
+        let mut output = Output::new(report.to_str().unwrap(), false).unwrap();
+        let mut ctx = CmdContext::default().with_dry_run(cli.dry_run);
+        let result = cli.cmd.execute(&mut ctx).await.unwrap();
+        output.print(result).unwrap();
+        output.finish(&ctx.take_skipped()).unwrap();
+        assert!(!target.exists());
+        let printed = fs::read_to_string(&report).unwrap();
+        assert!(printed.starts_with("Would write "));
+        assert!(printed.trim_end().ends_with(target.to_str().unwrap()));
+    }
+}
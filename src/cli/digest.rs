@@ -0,0 +1,156 @@
+use super::{validate_file, CmdExecutor};
+use crate::{
+    check_stdin_input, process_digest_check, process_digest_files, process_verify_manifest,
+    CliError, CmdContext, CmdOutput,
+};
+use clap::{builder::RangedU64ValueParser, Args, ValueEnum, ValueHint};
+use serde_json::json;
+use std::fmt;
+
+#[derive(Debug, Args)]
+pub struct DigestOpts {
+    /// the digest algorithm
+    #[arg(short, long, value_enum, default_value = "sha256")]
+    pub algo: DigestAlgo,
+
+    /// files to hash, from file or stdin
+    #[arg(value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
+    pub files: Vec<String>,
+
+    /// how many files to hash at once, default: the number of CPUs
+    #[arg(short, long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub jobs: Option<usize>,
+
+    /// check the files listed in a checksum manifest
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, conflicts_with = "files")]
+    pub check: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyFileOpts {
+    /// the manifest listing `<algo>:<hex>  <path>` entries
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath)]
+    pub manifest: String,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DigestAlgo {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl CmdExecutor for DigestOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let algo = self.algo.to_string();
+        if let Some(manifest) = self.check {
+            let results = process_digest_check(&manifest, &algo)?;
+            let output = check_results("digest check", &results, ["OK", "FAILED"]);
+            return Ok(fail_on_mismatch(
+                output,
+                &results,
+                "computed checksums did NOT match",
+            ));
+        }
+
+        for file in &self.files {
+            check_stdin_input(file, "the files to hash")?;
+        }
+        let digests = process_digest_files(&self.files, &algo, self.jobs)?
+            .into_iter()
+            .zip(self.files)
+            .collect::<Vec<_>>();
+        let files = digests
+            .iter()
+            .map(|(digest, name)| json!({ "file": name, "digest": digest }))
+            .collect::<Vec<_>>();
+        Ok(CmdOutput::Json {
+            json: json!({
+                "command": "digest",
+                "algo": algo,
+                "files": files,
+            }),
+            lines: digests
+                .iter()
+                .map(|(digest, name)| format!("{}  {}", digest, name))
+                .collect(),
+        })
+    }
+}
+
+impl CmdExecutor for VerifyFileOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let results = process_verify_manifest(&self.manifest)?;
+        let output = check_results("verify-file", &results, ["PASS", "FAIL"]);
+        Ok(fail_on_mismatch(
+            output,
+            &results,
+            "files failed verification",
+        ))
+    }
+}
+
+// One line per file, with the `[ok, failed]` word for its result.
+fn check_results(command: &str, results: &[(String, bool)], words: [&str; 2]) -> CmdOutput {
+    let files = results
+        .iter()
+        .map(|(name, ok)| json!({ "file": name, "ok": ok }))
+        .collect::<Vec<_>>();
+    CmdOutput::Json {
+        json: json!({
+            "command": command,
+            "files": files,
+        }),
+        lines: results
+            .iter()
+            .map(|(name, ok)| format!("{}: {}", name, if *ok { words[0] } else { words[1] }))
+            .collect(),
+    }
+}
+
+// The results still print when some files failed, then rcli exits with 3.
+fn fail_on_mismatch(output: CmdOutput, results: &[(String, bool)], reason: &str) -> CmdOutput {
+    let failed = results.iter().filter(|(_, ok)| !ok).count();
+    if failed == 0 {
+        return output;
+    }
+    CmdOutput::Failed {
+        output: Box::new(output),
+        error: CliError::Verification(format!("{} of {} {}", failed, results.len(), reason)).into(),
+    }
+}
+
+impl fmt::Display for DigestAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DigestAlgo::Sha256 => write!(f, "sha256"),
+            DigestAlgo::Sha512 => write!(f, "sha512"),
+            DigestAlgo::Blake3 => write!(f, "blake3"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::run_with_output;
+
+    #[tokio::test]
+    async fn test_digest_generate_then_check() {
+        let args = ["digest", "-a", "blake3", "assets/juventus.csv"];
+        let (manifest, result) = run_with_output(&args).await;
+        result.unwrap();
+        assert!(manifest.ends_with("  assets/juventus.csv\n"));
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_str().unwrap();
+        std::fs::write(path, &manifest).unwrap();
+        let (output, result) = run_with_output(&["digest", "-a", "blake3", "-c", path]).await;
+        result.unwrap();
+        assert_eq!(output, "assets/juventus.csv: OK\n");
+
+        std::fs::write(path, manifest.replace("  ", "0  ")).unwrap();
+        let (output, result) = run_with_output(&["digest", "-a", "blake3", "-c", path]).await;
+        assert!(result.is_err());
+        assert_eq!(output, "assets/juventus.csv: FAILED\n");
+    }
+}
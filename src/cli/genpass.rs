@@ -1,5 +1,5 @@
 use super::CmdExecutor;
-use crate::process_genpass;
+use crate::{process_genpass, CmdContext, CmdOutput};
 use clap::Args;
 use zxcvbn::zxcvbn;
 
@@ -27,7 +27,7 @@ pub struct GenPassOpts {
 }
 
 impl CmdExecutor for GenPassOpts {
-    async fn execute(self) -> anyhow::Result<()> {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
         let pass = process_genpass(
             self.length,
             self.lower,
@@ -35,12 +35,51 @@ impl CmdExecutor for GenPassOpts {
             self.digits,
             self.symbol,
         )?;
-        unsafe {
-            let password = String::from_utf8_unchecked(pass);
-            println!("{}", password);
-            let estimate = zxcvbn(&password, &[])?;
-            eprintln!("Estimated strength: {}\n", estimate.score());
-        }
-        Ok(())
+        let password = unsafe { String::from_utf8_unchecked(pass) };
+        let strength = zxcvbn(&password, &[])?.score();
+        Ok(CmdOutput::Password { password, strength })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_with_output;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_execute_returns_password() {
+        let opts = GenPassOpts {
+            length: 24,
+            lower: true,
+            upper: true,
+            digits: true,
+            symbol: false,
+        };
+        let result = opts.execute(&mut CmdContext::default()).await.unwrap();
+        let CmdOutput::Password { password, strength } = result else {
+            panic!("genpass should return a password, got {:?}", result);
+        };
+        assert_eq!(password.len(), 24);
+        assert!(password.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert!(strength <= 4);
+    }
+
+    #[tokio::test]
+    async fn test_genpass_to_output_file() {
+        let (output, result) = run_with_output(&["genpass", "-l", "24"]).await;
+        result.unwrap();
+        assert_eq!(output.trim_end().len(), 24);
+    }
+
+    #[tokio::test]
+    async fn test_genpass_json_output() {
+        let (output, result) = run_with_output(&["--json", "genpass", "-l", "24"]).await;
+        result.unwrap();
+        let value = serde_json::from_str::<Value>(&output).unwrap();
+        assert_eq!(value["command"], "genpass");
+        assert_eq!(value["password"].as_str().unwrap().len(), 24);
+        assert!(value["strength"].as_u64().unwrap() <= 4);
+        assert_eq!(value.as_object().unwrap().len(), 3);
     }
 }
@@ -0,0 +1,58 @@
+use super::{validate_file, CmdExecutor};
+use crate::{process_hex_decode, process_hex_encode, CmdContext, CmdOutput};
+use clap::{Args, Subcommand, ValueHint};
+use enum_dispatch::enum_dispatch;
+use serde_json::json;
+
+#[derive(Debug, Subcommand)]
+#[enum_dispatch(CmdExecutor)]
+pub enum HexCommand {
+    /// Hex encode
+    #[command(name = "encode")]
+    Encode(HexEncodeOpts),
+
+    /// Hex decode, ignoring whitespace and `0x` prefixes
+    #[command(name = "decode")]
+    Decode(HexDecodeOpts),
+}
+
+#[derive(Debug, Args)]
+pub struct HexEncodeOpts {
+    /// input from stdin or file to encode
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
+    pub input: String,
+
+    /// use uppercase hex digits
+    #[arg(short, long)]
+    pub uppercase: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct HexDecodeOpts {
+    /// input from stdin or file to decode
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
+    pub input: String,
+}
+
+impl CmdExecutor for HexEncodeOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let encoded = process_hex_encode(&self.input, self.uppercase)?;
+        Ok(CmdOutput::Json {
+            json: json!({ "command": "hex encode", "result": encoded }),
+            lines: vec![encoded],
+        })
+    }
+}
+
+impl CmdExecutor for HexDecodeOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let decoded = process_hex_decode(&self.input)?;
+        match String::from_utf8(decoded) {
+            Ok(result) => Ok(CmdOutput::Json {
+                json: json!({ "command": "hex decode", "result": result }),
+                lines: vec![result],
+            }),
+            Err(e) => Ok(CmdOutput::Bytes(e.into_bytes())),
+        }
+    }
+}
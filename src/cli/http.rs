@@ -1,8 +1,8 @@
-use super::{validate_addr, validate_path, validate_port, CmdExecutor};
-use crate::process_http_serve;
-use clap::{Args, Subcommand};
+use super::{validate_addr, validate_file_or_dir, validate_port, validate_size, CmdExecutor};
+use crate::{process_http_serve, CliError, CmdContext, CmdOutput, HttpServeConfig};
+use clap::{Args, Subcommand, ValueEnum, ValueHint};
 use enum_dispatch::enum_dispatch;
-use std::{net::IpAddr, path::PathBuf};
+use std::{fmt, net::IpAddr, path::PathBuf};
 
 #[enum_dispatch(CmdExecutor)]
 #[derive(Debug, Subcommand)]
@@ -25,18 +25,261 @@ pub struct HttpServerOpts {
     #[arg(short, long, value_parser = validate_port, default_value = "8080")]
     pub port: u16,
 
-    /// file service root path
-    #[arg(long, value_parser = validate_path)]
-    pub path: PathBuf,
+    /// file service root path, or a single file to serve at "/", default: the current dir
+    #[arg(long, value_parser = validate_file_or_dir, value_hint = ValueHint::AnyPath)]
+    pub path: Option<PathBuf>,
 
     /// whether to start as a daemon
     #[arg(short, long)]
     pub daemon: bool,
+
+    /// whether to follow symlinks that point outside the root path
+    #[arg(long)]
+    pub follow_symlinks: bool,
+
+    /// whether to enable WebDAV methods (PROPFIND, MKCOL, MOVE)
+    #[arg(long)]
+    pub webdav: bool,
+
+    /// the log level of the server, unless -v or -q is given
+    #[arg(long, value_enum, default_value = "info")]
+    pub log_level: LogLevel,
+
+    /// the index file served for a directory instead of the listing
+    #[arg(long, default_value = "index.html")]
+    pub index: String,
+
+    /// whether to serve the root index file for unknown paths (single-page apps)
+    #[arg(long)]
+    pub spa: bool,
+
+    /// glob patterns of files to hide and refuse to serve, like: .git, *.key
+    #[arg(long)]
+    pub exclude: Vec<String>,
+
+    /// the color theme of the directory listing
+    #[arg(long, value_enum, default_value = "auto")]
+    pub theme: Theme,
+
+    /// a minijinja template for the directory listing instead of the built-in one
+    #[arg(long, value_parser = validate_file_or_dir, value_hint = ValueHint::FilePath)]
+    pub template: Option<PathBuf>,
+
+    /// whether to print a QR code of the server url on startup
+    #[arg(long)]
+    pub qr: bool,
+
+    /// whether to export Prometheus metrics on /metrics
+    #[arg(long)]
+    pub metrics: bool,
+
+    /// the maximum request body size, like: 512KB, 10MB, 1GB
+    #[arg(long, value_parser = validate_size)]
+    pub max_body: Option<usize>,
+
+    /// extra response header, like: "Cache-Control: max-age=3600"
+    #[arg(long = "header")]
+    pub headers: Vec<String>,
+
+    /// whether to serve files as attachments instead of rendering them inline
+    #[arg(long)]
+    pub force_download: bool,
+
+    /// override the content type of an extension, like: .md=text/markdown
+    #[arg(long)]
+    pub mime: Vec<String>,
+
+    /// require basic auth credentials under a path, like: /private:user:pass
+    #[arg(long)]
+    pub protect: Vec<String>,
+
+    /// whether to open the browser once the server is listening
+    #[arg(long)]
+    pub open: bool,
+
+    /// open the browser even if the server is bound to a public address
+    #[arg(long)]
+    pub force_open: bool,
+
+    /// whether to accept HTTP/2 over cleartext (h2c) besides HTTP/1.1
+    #[arg(long)]
+    pub http2: bool,
+
+    /// seconds an idle connection is kept open, 0 disables keep-alive
+    #[arg(long, default_value = "75")]
+    pub keep_alive: u64,
+
+    /// seconds to read a request's headers and answer a read before giving up,
+    /// uploads, moves and deletes aren't limited, 0 means no limit
+    #[arg(long, default_value = "30")]
+    pub request_timeout: u64,
+
+    /// whether clients may only read files or also upload, move and delete them
+    #[arg(long, value_enum, default_value = "ro")]
+    pub mode: Mode,
+
+    /// whether to serve an existing `.gz` sibling to clients accepting gzip
+    #[arg(long)]
+    pub precompressed: bool,
+
+    /// only accept clients from this network, like: 192.168.0.0/16
+    #[arg(long)]
+    pub allow: Vec<String>,
+
+    /// refuse clients from this network, like: 10.0.0.0/8
+    #[arg(long)]
+    pub deny: Vec<String>,
+}
+
+#[derive(Debug, ValueEnum, Clone, Copy)]
+pub enum Theme {
+    /// follow the browser's preferred color scheme
+    Auto,
+
+    /// light theme
+    Light,
+
+    /// dark theme
+    Dark,
+}
+
+#[derive(Debug, ValueEnum, Clone, Copy)]
+pub enum LogLevel {
+    /// only errors
+    Error,
+
+    /// warnings and errors
+    Warn,
+
+    /// informational messages
+    Info,
+
+    /// debug messages
+    Debug,
+
+    /// all trace messages
+    Trace,
+}
+
+#[derive(Debug, ValueEnum, Clone, Copy)]
+pub enum Mode {
+    /// read-only, write requests are refused
+    Ro,
+
+    /// read-write, allow PUT, DELETE and WebDAV MKCOL/MOVE
+    Rw,
 }
 
 impl CmdExecutor for HttpServerOpts {
-    async fn execute(self) -> anyhow::Result<()> {
-        process_http_serve(self.path, &self.addr, self.port, self.daemon).await?;
-        Ok(())
+    async fn execute(self, ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let config = HttpServeConfig {
+            path: root_path(self.path, ctx)?,
+            addr: self.addr,
+            port: self.port,
+            daemon: self.daemon,
+            follow_symlinks: self.follow_symlinks,
+            webdav: self.webdav,
+            index: self.index,
+            spa: self.spa,
+            exclude: self.exclude,
+            theme: self.theme.to_string(),
+            template: self.template,
+            qr: self.qr,
+            metrics: self.metrics,
+            max_body: self.max_body,
+            headers: self.headers,
+            force_download: self.force_download,
+            mime: self.mime,
+            protect: self.protect,
+            open: self.open,
+            force_open: self.force_open,
+            http2: self.http2,
+            keep_alive: self.keep_alive,
+            request_timeout: self.request_timeout,
+            mode: self.mode.to_string(),
+            precompressed: self.precompressed,
+            allow: self.allow,
+            deny: self.deny,
+        };
+        // Serving never finishes, the QR code is printed as soon as it's ready.
+        process_http_serve(config, &mut std::io::stdout()).await?;
+        Ok(CmdOutput::None)
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Theme::Auto => write!(f, "auto"),
+            Theme::Light => write!(f, "light"),
+            Theme::Dark => write!(f, "dark"),
+        }
+    }
+}
+
+// The --path, asked for when it's missing and --interactive is given, else
+// the current dir.
+fn root_path(path: Option<PathBuf>, ctx: &mut CmdContext) -> anyhow::Result<PathBuf> {
+    match path {
+        Some(path) => Ok(path),
+        None if ctx.is_interactive() => {
+            let path = ctx.ask("--path")?;
+            Ok(validate_file_or_dir(path.trim()).map_err(CliError::Usage)?)
+        }
+        None => Ok(PathBuf::from(".")),
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogLevel::Error => write!(f, "error"),
+            LogLevel::Warn => write!(f, "warn"),
+            LogLevel::Info => write!(f, "info"),
+            LogLevel::Debug => write!(f, "debug"),
+            LogLevel::Trace => write!(f, "trace"),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Ro => write!(f, "ro"),
+            Mode::Rw => write!(f, "rw"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Commands};
+    use clap::Parser;
+
+    #[test]
+    fn test_root_path_asked_interactively() {
+        let dir = tempfile::tempdir().unwrap();
+        let answer = dir.path().to_str().unwrap().to_string();
+        let mut ctx = CmdContext::default().with_prompt(move |name| {
+            assert_eq!(name, "--path");
+            Ok(format!("{}\n", answer))
+        });
+        assert_eq!(root_path(None, &mut ctx).unwrap(), dir.path());
+
+        let mut ctx = CmdContext::default().with_prompt(|_| Ok("/no/such/dir".into()));
+        let err = root_path(None, &mut ctx).unwrap_err();
+        assert_eq!(crate::exit_code(&err), 2);
+    }
+
+    #[test]
+    fn test_serve_defaults_to_current_dir() {
+        let cli = Cli::try_parse_from(["rcli", "serve"]).unwrap();
+        let Commands::Serve(opts) = cli.cmd else {
+            panic!("rcli serve should parse as Commands::Serve");
+        };
+        assert_eq!(opts.port, 8080);
+        let mut ctx = CmdContext::default();
+        assert_eq!(root_path(opts.path, &mut ctx).unwrap(), PathBuf::from("."));
     }
 }
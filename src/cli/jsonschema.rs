@@ -0,0 +1,49 @@
+use super::{validate_file, CmdExecutor};
+use crate::{process_json_validate, CliError, CmdContext, CmdOutput};
+use clap::{Args, ValueHint};
+use serde_json::json;
+
+#[derive(Debug, Args)]
+pub struct JsonSchemaOpts {
+    /// the JSON Schema file
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath)]
+    pub schema: String,
+
+    /// the JSON document, or a CSV file validated as `csv` converts it
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
+    pub input: String,
+}
+
+impl CmdExecutor for JsonSchemaOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let errors = process_json_validate(&self.schema, &self.input)?;
+        let details = errors
+            .iter()
+            .map(|(path, message)| json!({ "path": path, "message": message }))
+            .collect::<Vec<_>>();
+        let lines = if errors.is_empty() {
+            vec!["valid".to_string()]
+        } else {
+            errors
+                .iter()
+                .map(|(path, message)| format!("{}: {}", path, message))
+                .collect()
+        };
+        let output = CmdOutput::Json {
+            json: json!({
+                "command": "jsonschema",
+                "valid": errors.is_empty(),
+                "errors": details,
+            }),
+            lines,
+        };
+        if errors.is_empty() {
+            return Ok(output);
+        }
+        Ok(CmdOutput::Failed {
+            output: Box::new(output),
+            error: CliError::Verification(format!("{} schema validation errors", errors.len()))
+                .into(),
+        })
+    }
+}
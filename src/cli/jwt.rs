@@ -1,11 +1,20 @@
-use super::{validate_exp_time, CmdExecutor};
-use crate::{process_jwt_sign_with_secret, process_jwt_verify_with_secret};
-use anyhow::Result;
-use clap::{Args, Subcommand, ValueEnum};
+use super::{validate_duration, validate_exp_time, validate_path, CmdExecutor};
+#[cfg(feature = "jwks")]
+use crate::process_jwt_fetch_jwks;
+use crate::{
+    get_reader, process_jwt_decode, process_jwt_genkey, process_jwt_parse_jwks,
+    process_jwt_sign_with_secret, process_jwt_verify_with_header, process_jwt_verify_with_jwks,
+    read_contents, read_secret, CliError, CmdContext, CmdOutput, VerifyOptions,
+};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, Utc};
+use clap::{Args, Subcommand, ValueEnum, ValueHint};
 use core::fmt;
 use enum_dispatch::enum_dispatch;
-use serde::{Deserialize, Serialize};
-use std::ops::Deref;
+use jsonwebtoken::{jwk::JwkSet, Header};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Value};
+use std::{io::Read, ops::Deref, path::PathBuf};
 
 #[enum_dispatch(CmdExecutor)]
 #[derive(Debug, Subcommand)]
@@ -17,6 +26,14 @@ pub enum JwtCommand {
     /// Verify a jwt token with a shared secret.
     #[command(name = "verify")]
     Verify(JwtVerifyOpts),
+
+    /// Print the header and claims of a jwt token WITHOUT verifying it.
+    #[command(name = "decode")]
+    Decode(JwtDecodeOpts),
+
+    /// Generate a random sign secret, or a key pair for rs256/es256/eddsa.
+    #[command(name = "genkey")]
+    GenKey(JwtGenKeyOpts),
 }
 
 #[derive(Debug, Args)]
@@ -25,72 +42,582 @@ pub struct JwtSignOpts {
     #[command(flatten)]
     pub payload: Payload,
 
-    /// the sign secret
-    #[arg(short, long)]
-    pub key: String,
+    /// read the payload from a JSON object file instead, or "-" for stdin
+    #[arg(
+        long,
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["sub", "aud", "exp", "no_exp", "nbf", "iss"]
+    )]
+    pub payload_file: Option<String>,
+
+    /// the sign secret, or the private key file (PEM) for rs256/es256/eddsa,
+    /// else read from RCLI_JWT_KEY
+    #[arg(short, long, value_hint = ValueHint::FilePath, conflicts_with = "key_file")]
+    pub key: Option<String>,
+
+    /// read the sign secret or key from a file, or "-" for stdin
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub key_file: Option<String>,
 
     /// the signature algorithm
     #[arg(long, value_enum, default_value = "hs256")]
     pub alg: JwtAlgorithm,
+
+    /// an extra claim, like: role=admin, admin=true, level=3
+    #[arg(long = "claim", value_parser = parse_claim)]
+    pub claims: Vec<(String, Value)>,
+
+    /// whether to set the issued at field to the current time
+    #[arg(long)]
+    pub iat: bool,
+
+    /// the key id header, to pick the verify key when keys are rotated
+    #[arg(long)]
+    pub kid: Option<String>,
 }
 
 #[derive(Debug, Args)]
 pub struct JwtVerifyOpts {
-    /// the jwt token to be verified
-    #[arg(short, long)]
-    pub token: String,
+    /// the jwt token to be verified, a `Bearer ` prefix is ignored
+    #[arg(
+        short,
+        long,
+        required_unless_present = "token_file",
+        conflicts_with = "token_file"
+    )]
+    pub token: Option<String>,
 
-    /// the verify secret
-    #[arg(short, long)]
-    pub key: String,
+    /// read the token from a file instead, or "-" for stdin
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub token_file: Option<String>,
 
-    /// the signature algorithm
+    /// the verify secret, or the public key file (PEM) for rs256/es256/eddsa,
+    /// else read from RCLI_JWT_KEY
+    #[arg(
+        short,
+        long,
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["key_file", "jwk_file", "jwks_url"]
+    )]
+    pub key: Option<String>,
+
+    /// read the verify secret or key from a file, or "-" for stdin
+    #[arg(long, value_hint = ValueHint::FilePath, conflicts_with_all = ["jwk_file", "jwks_url"])]
+    pub key_file: Option<String>,
+
+    /// a JWK or JWK set file, the key is picked by the token's kid
+    #[arg(long, value_hint = ValueHint::FilePath, conflicts_with_all = ["jwks_url", "alg"])]
+    pub jwk_file: Option<String>,
+
+    /// fetch the JWK set over https, like an OIDC provider's jwks_uri
+    #[arg(long, conflicts_with = "alg")]
+    pub jwks_url: Option<String>,
+
+    /// the expected signature algorithm, else the token's own if it fits the key:
+    /// RS256, ES256 or EdDSA for a PEM key, HMAC for a secret (`none` is always rejected)
     #[arg(long, value_enum)]
     pub alg: Option<JwtAlgorithm>,
+
+    /// the expected issuer
+    #[arg(long)]
+    pub iss: Option<String>,
+
+    /// the expected audience, the token is accepted if any of them matches
+    #[arg(long)]
+    pub aud: Vec<String>,
+
+    /// seconds of clock skew to tolerate when checking exp and nbf
+    #[arg(long, default_value = "60")]
+    pub leeway: u64,
+
+    /// claims that must be present and non-empty, like: sub,aud,role
+    #[arg(long, value_delimiter = ',')]
+    pub require: Vec<String>,
+
+    /// warn on stderr if the token expires within this time, like: 10m, 1h, 7d
+    #[arg(long, value_parser = validate_duration)]
+    pub warn_before: Option<u64>,
+
+    /// whether to accept tokens that aren't valid yet (nbf in the future)
+    #[arg(long)]
+    pub ignore_nbf: bool,
+
+    /// whether to print the claims as single line JSON
+    #[arg(long)]
+    pub compact: bool,
+
+    /// whether to add readable local times for exp, iat and nbf
+    #[arg(long)]
+    pub human: bool,
+
+    /// whether to print the token header (alg, typ, kid) along with the claims
+    #[arg(long)]
+    pub show_header: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct JwtDecodeOpts {
+    /// the jwt token to be decoded
+    #[arg(short, long)]
+    pub token: String,
+}
+
+#[derive(Debug, Args)]
+pub struct JwtGenKeyOpts {
+    /// the signature algorithm the key is for
+    #[arg(long, value_enum, default_value = "hs256")]
+    pub alg: JwtAlgorithm,
+
+    /// save the key to a dir instead of printing it
+    #[arg(short, long, value_parser = validate_path, value_hint = ValueHint::DirPath)]
+    pub output: Option<PathBuf>,
 }
 
 #[derive(Debug, ValueEnum, Clone, Copy)]
 pub enum JwtAlgorithm {
     /// HMAC SHA256 algorithm
     HS256,
+
+    /// HMAC SHA384 algorithm
+    HS384,
+
+    /// HMAC SHA512 algorithm
+    HS512,
+
+    /// RSA PKCS#1 v1.5 SHA256 algorithm
+    RS256,
+
+    /// ECDSA P-256 SHA256 algorithm
+    ES256,
+
+    /// Ed25519 signature algorithm
+    #[value(name = "eddsa")]
+    EdDSA,
+}
+
+// The `aud` claim is a string for a single audience, otherwise an array.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
 }
 
 #[derive(Debug, Serialize, Deserialize, Args)]
 pub struct Payload {
     /// the subject field
-    #[arg(long)]
-    pub sub: String,
+    #[arg(long, required_unless_present = "payload_file")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
 
-    /// the audience field
-    #[arg(long)]
-    pub aud: String,
+    /// the audience field, repeat it for several audiences
+    #[arg(long, required_unless_present = "payload_file")]
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_aud",
+        deserialize_with = "deserialize_aud"
+    )]
+    pub aud: Vec<String>,
 
-    /// the expiration time field, like, 1m, 1h, 1d, 1w, 1M
-    #[arg(long, value_parser = validate_exp_time)]
-    pub exp: u64,
+    /// the expiration time field, like, 1m, 1h, 1d, 1w, 1M, or -1h for an expired token
+    #[arg(
+        long,
+        value_parser = validate_exp_time,
+        allow_hyphen_values = true,
+        required_unless_present_any = ["payload_file", "no_exp"]
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+
+    /// whether to omit the expiration time, the token never expires
+    #[arg(long, conflicts_with = "exp")]
+    #[serde(skip)]
+    pub no_exp: bool,
+
+    /// the not before field, relative to now, like, 1m, 1h, 1d, or -1h
+    #[arg(long, value_parser = validate_exp_time, allow_hyphen_values = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<u64>,
+
+    /// the issuer field
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
 }
 
 impl CmdExecutor for JwtSignOpts {
-    async fn execute(self) -> Result<()> {
+    async fn execute(self, _ctx: &mut CmdContext) -> Result<CmdOutput> {
+        let key = load_key(
+            self.key.as_deref(),
+            self.key_file.as_deref(),
+            Some(self.alg),
+        )?;
+        let mut claims = match &self.payload_file {
+            Some(path) => read_payload(path)?,
+            None => match serde_json::to_value(&self.payload)? {
+                Value::Object(claims) => claims,
+                _ => Map::new(),
+            },
+        };
+        if self.iat {
+            claims.insert("iat".to_string(), Utc::now().timestamp().into());
+        }
+        claims.extend(self.claims);
         let token = process_jwt_sign_with_secret(
-            &self.payload,
-            self.key.as_bytes(),
+            &claims,
+            &key,
             &self.alg.to_string(),
+            self.kid.as_deref(),
         )?;
-        println!("{}", token);
-        Ok(())
+        Ok(CmdOutput::Text(token))
     }
 }
 
 impl CmdExecutor for JwtVerifyOpts {
-    async fn execute(self) -> Result<()> {
-        let data = process_jwt_verify_with_secret::<Payload>(
-            &self.token,
-            self.key.as_bytes(),
-            self.alg.as_deref(),
-        )?;
-        println!("{:?}", data);
+    async fn execute(self, _ctx: &mut CmdContext) -> Result<CmdOutput> {
+        let (header, mut data) = match self.verify().await {
+            Ok(verified) => verified,
+            // Only --json shows anything for a failure, besides the error.
+            Err(e) => {
+                return Ok(CmdOutput::Failed {
+                    output: Box::new(CmdOutput::Json {
+                        json: json!({
+                            "command": "jwt verify",
+                            "verified": false,
+                            "error": e.to_string(),
+                        }),
+                        lines: Vec::new(),
+                    }),
+                    error: e,
+                });
+            }
+        };
+        let now = Utc::now().timestamp();
+        let warning = self
+            .warn_before
+            .and_then(|window| expiry_warning(&data, window, now));
+        if self.human {
+            humanize_times(&mut data, now);
+        }
+        let mut lines = Vec::new();
+        if self.show_header {
+            lines.push(render_header(&header)?);
+        }
+        lines.push(render_claims(&data, self.compact)?);
+        let mut result = json!({
+            "command": "jwt verify",
+            "verified": true,
+            "header": header,
+            "claims": data,
+        });
+        if let Some(warning) = warning {
+            eprintln!("{}", warning);
+            result["warning"] = warning.into();
+        }
+        Ok(CmdOutput::Json {
+            json: result,
+            lines,
+        })
+    }
+}
+
+impl JwtVerifyOpts {
+    async fn verify(&self) -> Result<(Header, Map<String, Value>)> {
+        let token = match (&self.token, self.token_file.as_deref()) {
+            (Some(token), _) => token.clone(),
+            (None, Some(path)) => {
+                if path == "-" && self.key_file.as_deref() == Some("-") {
+                    return Err(anyhow!(
+                        "the token and the key can't both be read from stdin"
+                    ));
+                }
+                read_token(get_reader(path)?)?
+            }
+            (None, None) => return Err(anyhow!("either --token or --token-file is required")),
+        };
+        let token = strip_bearer(&token);
+        let audience = self.aud.iter().map(|aud| aud.as_str()).collect::<Vec<_>>();
+        let options = VerifyOptions {
+            issuer: self.iss.as_deref(),
+            audience: &audience,
+            leeway: self.leeway,
+            validate_nbf: !self.ignore_nbf,
+        };
+        let jwks = load_jwks(self.jwk_file.as_deref(), self.jwks_url.as_deref()).await?;
+        let (header, data) = match jwks {
+            Some(jwks) => {
+                process_jwt_verify_with_jwks::<Map<String, Value>>(token, &jwks, &options)?
+            }
+            None => {
+                // Without --alg, the token's claimed algorithm only picks whether -k is the
+                // secret or a PEM file, the key still has to fit it to verify.
+                let alg = self.alg.or_else(|| token_alg(token));
+                let key = load_key(self.key.as_deref(), self.key_file.as_deref(), alg)?;
+                process_jwt_verify_with_header::<Map<String, Value>>(
+                    token,
+                    &key,
+                    self.alg.as_deref(),
+                    &options,
+                )?
+            }
+        };
+        check_required(&data, &self.require)?;
+        Ok((header, data))
+    }
+}
+
+impl CmdExecutor for JwtDecodeOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> Result<CmdOutput> {
+        let (header, claims) = process_jwt_decode(&self.token)?;
+        Ok(CmdOutput::Text(render_decoded(&header, &claims)?))
+    }
+}
+
+impl CmdExecutor for JwtGenKeyOpts {
+    async fn execute(self, ctx: &mut CmdContext) -> Result<CmdOutput> {
+        let keys = process_jwt_genkey(&self.alg)?;
+        let Some(dir) = self.output else {
+            let keys = keys.iter().map(|key| key.trim_end()).collect::<Vec<_>>();
+            return Ok(CmdOutput::Text(keys.join("\n")));
+        };
+        let names: &[&str] = match self.alg {
+            JwtAlgorithm::HS256 | JwtAlgorithm::HS384 | JwtAlgorithm::HS512 => &["jwt_secret.txt"],
+            JwtAlgorithm::RS256 => &["rsa_private.pem", "rsa_public.pem"],
+            JwtAlgorithm::ES256 => &["ec_private.pem", "ec_public.pem"],
+            JwtAlgorithm::EdDSA => &["ed25519_private.pem", "ed25519_public.pem"],
+        };
+        let mut written = Vec::new();
+        for (name, key) in names.iter().zip(keys) {
+            let path = dir.join(name);
+            ctx.write_file(&path, key)?;
+            if !ctx.is_dry_run() {
+                written.push(format!("Wrote {}", path.display()));
+            }
+        }
+        if written.is_empty() {
+            return Ok(CmdOutput::None);
+        }
+        Ok(CmdOutput::Text(written.join("\n")))
+    }
+}
+
+fn check_required(claims: &Map<String, Value>, required: &[String]) -> Result<()> {
+    let missing: Vec<_> = required
+        .iter()
+        .filter(|name| match claims.get(name.as_str()) {
+            None | Some(Value::Null) => true,
+            Some(Value::String(s)) => s.is_empty(),
+            Some(Value::Array(a)) => a.is_empty(),
+            Some(Value::Object(o)) => o.is_empty(),
+            Some(_) => false,
+        })
+        .map(|name| name.as_str())
+        .collect();
+    if missing.is_empty() {
         Ok(())
+    } else {
+        Err(
+            CliError::Verification(format!("missing required claims: {}", missing.join(", ")))
+                .into(),
+        )
+    }
+}
+
+fn expiry_warning(claims: &Map<String, Value>, window: u64, now: i64) -> Option<String> {
+    let exp = claims.get("exp").and_then(Value::as_i64)?;
+    let left = exp - now;
+    // Already expired tokens don't pass verification (except within the leeway).
+    (left < window as i64).then(|| {
+        format!(
+            "WARNING: the token expires in {}",
+            format_duration(left.max(0))
+        )
+    })
+}
+
+// Add `<claim>_time` next to each timestamp claim, and how long the token stays valid.
+fn humanize_times(claims: &mut Map<String, Value>, now: i64) {
+    for name in ["exp", "iat", "nbf"] {
+        let time = claims
+            .get(name)
+            .and_then(Value::as_i64)
+            .and_then(|ts| DateTime::from_timestamp(ts, 0));
+        if let Some(time) = time {
+            let local = time.with_timezone(&Local);
+            claims.insert(format!("{}_time", name), local.to_rfc3339().into());
+        }
+    }
+    if let Some(exp) = claims.get("exp").and_then(Value::as_i64) {
+        let remaining = if exp > now {
+            format!("expires in {}", format_duration(exp - now))
+        } else {
+            format!("expired {} ago", format_duration(now - exp))
+        };
+        claims.insert("validity".to_string(), remaining.into());
+    }
+}
+
+// Like: 1d 2h 3m 4s
+fn format_duration(secs: i64) -> String {
+    let units = [("d", 86400), ("h", 3600), ("m", 60), ("s", 1)];
+    let mut rest = secs;
+    let parts: Vec<_> = units
+        .iter()
+        .filter_map(|(unit, size)| {
+            let n = rest / size;
+            rest %= size;
+            (n > 0).then(|| format!("{}{}", n, unit))
+        })
+        .collect();
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+fn render_claims(claims: &impl Serialize, compact: bool) -> Result<String> {
+    let output = if compact {
+        serde_json::to_string(claims)?
+    } else {
+        serde_json::to_string_pretty(claims)?
+    };
+    Ok(output)
+}
+
+fn render_header(header: &impl Serialize) -> Result<String> {
+    Ok(format!("Header: {}", serde_json::to_string_pretty(header)?))
+}
+
+fn render_decoded(header: &impl Serialize, claims: &Value) -> Result<String> {
+    Ok(format!(
+        "WARNING: the signature was NOT verified\n{}\nClaims: {}",
+        render_header(header)?,
+        render_claims(claims, false)?
+    ))
+}
+
+fn serialize_aud<S: Serializer>(aud: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    match aud {
+        [one] => serializer.serialize_str(one),
+        many => many.serialize(serializer),
+    }
+}
+
+fn deserialize_aud<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(match Audience::deserialize(deserializer)? {
+        Audience::One(one) => vec![one],
+        Audience::Many(many) => many,
+    })
+}
+
+// Accept an Authorization header value copied as is, like `Bearer eyJ...`.
+fn strip_bearer(token: &str) -> &str {
+    let token = token.trim();
+    match token.split_once(' ') {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("bearer") => rest.trim_start(),
+        _ => token,
+    }
+}
+
+// Surrounding whitespace, like the newline `echo` adds, isn't part of the token.
+fn read_token(mut reader: impl Read) -> Result<String> {
+    let mut token = String::new();
+    reader.read_to_string(&mut token)?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(anyhow!("the token is empty"));
+    }
+    Ok(token.to_string())
+}
+
+fn read_payload(path: &str) -> Result<Map<String, Value>> {
+    match serde_json::from_slice(&read_contents(path)?)? {
+        Value::Object(claims) => Ok(claims),
+        _ => Err(anyhow!("the payload must be a JSON object")),
+    }
+}
+
+// Values that parse as JSON (numbers, booleans, ...) are kept as such, others are strings.
+fn parse_claim(s: &str) -> Result<(String, Value), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => {
+            let value = serde_json::from_str(value).unwrap_or(Value::String(value.to_string()));
+            Ok((key.to_string(), value))
+        }
+        _ => Err(format!("`{}` isn't a valid claim, expected key=value", s)),
+    }
+}
+
+const KEY_ENV: &str = "RCLI_JWT_KEY";
+
+fn load_key(
+    key: Option<&str>,
+    key_file: Option<&str>,
+    alg: Option<JwtAlgorithm>,
+) -> Result<Vec<u8>> {
+    load_key_with(key, key_file, alg, std::env::var(KEY_ENV).ok())
+}
+
+// HMAC algorithms take the secret itself, the others a PEM key file.
+// Without either flag the key comes from RCLI_JWT_KEY, given as `env`,
+// which holds the secret or the PEM key itself.
+fn load_key_with(
+    key: Option<&str>,
+    key_file: Option<&str>,
+    alg: Option<JwtAlgorithm>,
+    env: Option<String>,
+) -> Result<Vec<u8>> {
+    if let Some(key_file) = key_file {
+        let mut key = read_contents(key_file)?;
+        // Editors and `echo` leave a trailing newline which isn't part of the secret.
+        if key.ends_with(b"\n") {
+            key.pop();
+            if key.ends_with(b"\r") {
+                key.pop();
+            }
+        }
+        return Ok(key);
+    }
+    let hmac = matches!(
+        alg,
+        None | Some(JwtAlgorithm::HS256 | JwtAlgorithm::HS384 | JwtAlgorithm::HS512)
+    );
+    let Some(key) = key else {
+        // Only a secret can be typed at the prompt, PEM keys come from the variable.
+        let secret = match hmac {
+            true => read_secret(env, "Jwt secret: ")?,
+            false => env.filter(|key| !key.is_empty()),
+        };
+        return secret
+            .map(String::into_bytes)
+            .ok_or_else(|| anyhow!("either --key, --key-file or {} is required", KEY_ENV));
+    };
+    match hmac {
+        true => Ok(key.as_bytes().to_vec()),
+        false => read_contents(key),
+    }
+}
+
+// The algorithm in the token header, if it's one rcli knows.
+fn token_alg(token: &str) -> Option<JwtAlgorithm> {
+    let (header, _) = process_jwt_decode(token).ok()?;
+    JwtAlgorithm::from_str(&format!("{:?}", header.alg), true).ok()
+}
+
+async fn load_jwks(jwk_file: Option<&str>, jwks_url: Option<&str>) -> Result<Option<JwkSet>> {
+    if let Some(path) = jwk_file {
+        return process_jwt_parse_jwks(&read_contents(path)?).map(Some);
+    }
+    match jwks_url {
+        #[cfg(feature = "jwks")]
+        Some(url) => process_jwt_fetch_jwks(url).await.map(Some),
+        #[cfg(not(feature = "jwks"))]
+        Some(_) => Err(anyhow!(
+            "--jwks-url needs rcli to be built with the `jwks` feature"
+        )),
+        None => Ok(None),
     }
 }
 
@@ -98,6 +625,11 @@ impl fmt::Display for JwtAlgorithm {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             JwtAlgorithm::HS256 => write!(f, "HS256"),
+            JwtAlgorithm::HS384 => write!(f, "HS384"),
+            JwtAlgorithm::HS512 => write!(f, "HS512"),
+            JwtAlgorithm::RS256 => write!(f, "RS256"),
+            JwtAlgorithm::ES256 => write!(f, "ES256"),
+            JwtAlgorithm::EdDSA => write!(f, "EdDSA"),
         }
     }
 }
@@ -108,6 +640,303 @@ impl Deref for JwtAlgorithm {
     fn deref(&self) -> &Self::Target {
         match self {
             JwtAlgorithm::HS256 => "HS256",
+            JwtAlgorithm::HS384 => "HS384",
+            JwtAlgorithm::HS512 => "HS512",
+            JwtAlgorithm::RS256 => "RS256",
+            JwtAlgorithm::ES256 => "ES256",
+            JwtAlgorithm::EdDSA => "EdDSA",
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_with_output;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_claim() {
+        assert_eq!(
+            parse_claim("role=admin"),
+            Ok(("role".into(), json!("admin")))
+        );
+        assert_eq!(parse_claim("level=3"), Ok(("level".into(), json!(3))));
+        assert_eq!(parse_claim("admin=true"), Ok(("admin".into(), json!(true))));
+        assert_eq!(parse_claim("note=a=b"), Ok(("note".into(), json!("a=b"))));
+        assert!(parse_claim("role").is_err());
+        assert!(parse_claim("=admin").is_err());
+    }
+
+    #[test]
+    fn test_render_decoded() {
+        let token =
+            process_jwt_sign_with_secret(json!({ "sub": "test" }), b"secret", "HS256", None);
+        let (header, claims) = process_jwt_decode(&token.unwrap()).unwrap();
+        let output = render_decoded(&header, &claims).unwrap();
+        assert!(output.starts_with("WARNING: the signature was NOT verified"));
+        assert!(output.contains(r#""alg": "HS256""#));
+        assert!(output.contains(r#""sub": "test""#));
+    }
+
+    #[test]
+    fn test_payload_audience() {
+        let mut payload = Payload {
+            sub: Some("test".to_string()),
+            aud: vec!["api".to_string()],
+            exp: None,
+            no_exp: true,
+            nbf: None,
+            iss: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            json!({ "sub": "test", "aud": "api" })
+        );
+        payload.aud.push("web".to_string());
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(value, json!({ "sub": "test", "aud": ["api", "web"] }));
+        let payload: Payload = serde_json::from_value(value).unwrap();
+        assert_eq!(payload.aud, ["api", "web"]);
+    }
+
+    #[test]
+    fn test_check_required() {
+        let claims = json!({ "sub": "test", "aud": [], "role": "", "level": 0, "admin": false });
+        let claims = claims.as_object().unwrap();
+        let required = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert!(check_required(claims, &required(&["sub", "level", "admin"])).is_ok());
+        let err = check_required(claims, &required(&["sub", "aud", "role", "custom"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing required claims: aud, role, custom"
+        );
+    }
+
+    #[test]
+    fn test_expiry_warning() {
+        let now = 1_700_000_000;
+        let claims = json!({ "sub": "test", "exp": now + 30 });
+        let claims = claims.as_object().unwrap();
+        let window = validate_duration("1m").unwrap();
+        assert_eq!(
+            expiry_warning(claims, window, now).unwrap(),
+            "WARNING: the token expires in 30s"
+        );
+        assert!(expiry_warning(claims, 30, now).is_none());
+        let claims = json!({ "sub": "test" });
+        assert!(expiry_warning(claims.as_object().unwrap(), window, now).is_none());
+    }
+
+    #[test]
+    fn test_humanize_times() {
+        let now = 1_700_000_000;
+        let mut claims = json!({ "sub": "test", "iat": now, "exp": now + 90061 });
+        let claims = claims.as_object_mut().unwrap();
+        humanize_times(claims, now);
+        let iat = claims["iat_time"].as_str().unwrap();
+        assert!(DateTime::parse_from_rfc3339(iat).is_ok());
+        assert!(claims["exp_time"].as_str().unwrap().contains('T'));
+        assert_eq!(claims["validity"], "expires in 1d 1h 1m 1s");
+        assert_eq!(claims["iat"], now);
+        assert!(!claims.contains_key("nbf_time"));
+
+        humanize_times(claims, now + 90061 + 120);
+        assert_eq!(claims["validity"], "expired 2m ago");
+    }
+
+    #[test]
+    fn test_render_claims() {
+        let claims = json!({ "sub": "test", "exp": 1700000000, "roles": ["admin"] });
+        let pretty = render_claims(&claims, false).unwrap();
+        assert!(pretty.contains('\n'));
+        assert_eq!(serde_json::from_str::<Value>(&pretty).unwrap(), claims);
+        let compact = render_claims(&claims, true).unwrap();
+        assert!(!compact.contains('\n'));
+        assert_eq!(serde_json::from_str::<Value>(&compact).unwrap(), claims);
+    }
+
+    #[test]
+    fn test_verify_custom_claims() {
+        // No standard claims at all, and nested ones `Payload` knows nothing about.
+        let claims = json!({ "role": "admin", "scope": { "read": true }, "level": 3 });
+        let token = process_jwt_sign_with_secret(&claims, b"secret", "HS256", None).unwrap();
+        let (_, data) = process_jwt_verify_with_header::<Map<String, Value>>(
+            &token,
+            b"secret",
+            None,
+            &VerifyOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(Value::Object(data.clone()), claims);
+        assert_eq!(
+            render_claims(&data, true).unwrap(),
+            r#"{"level":3,"role":"admin","scope":{"read":true}}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_json_output() {
+        let claims = json!({ "sub": "test", "exp": Utc::now().timestamp() + 600 });
+        let token = process_jwt_sign_with_secret(&claims, b"secret", "HS256", None).unwrap();
+        let args = ["--json", "jwt", "verify", "-t", &token, "-k", "secret"];
+        let (output, result) = run_with_output(&args).await;
+        result.unwrap();
+        let value = serde_json::from_str::<Value>(&output).unwrap();
+        assert_eq!(value["command"], "jwt verify");
+        assert_eq!(value["verified"], true);
+        assert_eq!(value["header"]["alg"], "HS256");
+        assert_eq!(value["claims"], claims);
+
+        let args = ["--json", "jwt", "verify", "-t", &token, "-k", "wrong"];
+        let (output, result) = run_with_output(&args).await;
+        assert!(result.is_err());
+        let value = serde_json::from_str::<Value>(&output).unwrap();
+        assert_eq!(value["verified"], false);
+        assert!(value["error"]
+            .as_str()
+            .unwrap()
+            .contains("InvalidSignature"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_pem_key_without_alg() {
+        let claims = json!({ "sub": "test", "exp": Utc::now().timestamp() + 600 });
+        let private = std::fs::read("assets/rsa_private.pem").unwrap();
+        let token = process_jwt_sign_with_secret(&claims, &private, "RS256", None).unwrap();
+        let args = ["jwt", "verify", "-t", &token, "-k", "assets/rsa_public.pem"];
+        let (output, result) = run_with_output(&args).await;
+        result.unwrap();
+        assert!(output.contains(r#""sub": "test""#));
+        assert!(matches!(token_alg(&token), Some(JwtAlgorithm::RS256)));
+    }
+
+    #[test]
+    fn test_render_header() {
+        let claims = json!({ "sub": "test", "exp": Utc::now().timestamp() + 60 });
+        let token = process_jwt_sign_with_secret(&claims, b"secret", "HS512", None).unwrap();
+        let (header, _) = process_jwt_verify_with_header::<Value>(
+            &token,
+            b"secret",
+            None,
+            &VerifyOptions::default(),
+        )
+        .unwrap();
+        assert!(render_header(&header)
+            .unwrap()
+            .contains(r#""alg": "HS512""#));
+    }
+
+    #[test]
+    fn test_strip_bearer() {
+        let token =
+            crate::process_jwt_sign_with_secret(json!({ "sub": "test" }), b"secret", "HS256", None)
+                .unwrap();
+        for value in [
+            format!("Bearer {}", token),
+            format!("bearer  {}", token),
+            format!(" BEARER {}\n", token),
+            token.clone(),
+        ] {
+            assert_eq!(strip_bearer(&value), token);
+        }
+        let (_, claims) = process_jwt_verify_with_header::<Map<String, Value>>(
+            strip_bearer(&format!("Bearer {}", token)),
+            b"secret",
+            None,
+            &VerifyOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(claims["sub"], "test");
+    }
+
+    #[test]
+    fn test_read_token() {
+        let token =
+            crate::process_jwt_sign_with_secret(json!({ "sub": "test" }), b"secret", "HS256", None)
+                .unwrap();
+        // What a shell pipe like `echo $TOKEN | rcli jwt verify --token-file -` sends.
+        let piped = format!("  {}\n", token);
+        let read = read_token(piped.as_bytes()).unwrap();
+        assert_eq!(read, token);
+        let (_, claims) = process_jwt_verify_with_header::<Map<String, Value>>(
+            &read,
+            b"secret",
+            None,
+            &VerifyOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(claims["sub"], "test");
+        assert!(read_token(&b" \n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_payload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("payload.json");
+        let claims = json!({
+            "sub": "test",
+            "exp": Utc::now().timestamp() + 60,
+            "roles": ["admin", "dev"],
+            "org": { "id": 1 },
+        });
+        std::fs::write(&path, claims.to_string()).unwrap();
+        let payload = read_payload(path.to_str().unwrap()).unwrap();
+        let token = process_jwt_sign_with_secret(&payload, b"secret", "HS256", None).unwrap();
+        let data = process_jwt_verify_with_header::<Value>(
+            &token,
+            b"secret",
+            None,
+            &VerifyOptions::default(),
+        );
+        assert_eq!(data.unwrap().1, claims);
+
+        std::fs::write(&path, "[1, 2]").unwrap();
+        assert!(read_payload(path.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_load_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret.txt");
+        std::fs::write(&path, "secret\n").unwrap();
+        let path = path.to_str().unwrap();
+        let key = load_key(None, Some(path), Some(JwtAlgorithm::HS256)).unwrap();
+        assert_eq!(key, b"secret");
+
+        let claims = json!({ "sub": "test", "exp": Utc::now().timestamp() + 60 });
+        let token = process_jwt_sign_with_secret(&claims, &key, "HS256", None).unwrap();
+        let data = process_jwt_verify_with_header::<Value>(
+            &token,
+            b"secret",
+            None,
+            &VerifyOptions::default(),
+        );
+        assert_eq!(data.unwrap().1, claims);
+    }
+
+    #[test]
+    fn test_key_from_env() {
+        let env = Some("env-secret".to_string());
+        let key = load_key_with(None, None, Some(JwtAlgorithm::HS256), env).unwrap();
+        assert_eq!(key, b"env-secret");
+        let claims = json!({ "sub": "ci", "exp": Utc::now().timestamp() + 60 });
+        let token = process_jwt_sign_with_secret(&claims, &key, "HS256", None).unwrap();
+        let data = process_jwt_verify_with_header::<Value>(
+            &token,
+            b"env-secret",
+            None,
+            &VerifyOptions {
+                leeway: 0,
+                ..Default::default()
+            },
+        );
+        assert_eq!(data.unwrap().1["sub"], "ci");
+
+        let pem = std::fs::read_to_string("assets/rsa_private.pem").unwrap();
+        let key = load_key_with(None, None, Some(JwtAlgorithm::RS256), Some(pem.clone())).unwrap();
+        assert_eq!(key, pem.as_bytes());
+        let err = load_key_with(None, None, Some(JwtAlgorithm::RS256), Some(String::new()));
+        assert!(err.unwrap_err().to_string().contains("is required"));
+    }
+}
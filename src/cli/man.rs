@@ -0,0 +1,69 @@
+use super::{validate_path, Cli, CmdExecutor};
+use crate::{CmdContext, CmdOutput};
+use anyhow::Result;
+use clap::{Args, Command, CommandFactory, ValueHint};
+use clap_mangen::Man;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Args)]
+pub struct ManOpts {
+    /// the dir to write the man pages to
+    #[arg(short, long, value_parser = validate_path, value_hint = ValueHint::DirPath, default_value = ".")]
+    pub output: PathBuf,
+}
+
+impl CmdExecutor for ManOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> Result<CmdOutput> {
+        let mut cmd = Cli::command();
+        cmd.build();
+        let written = render_man_pages(&cmd, &self.output)?
+            .iter()
+            .map(|path| format!("Wrote {}", path.display()))
+            .collect::<Vec<_>>();
+        Ok(CmdOutput::Text(written.join("\n")))
+    }
+}
+
+// Write one page per command, named like `rcli-jwt-sign.1`, and return the paths.
+fn render_man_pages(cmd: &Command, dir: &Path) -> Result<Vec<PathBuf>> {
+    let man = Man::new(cmd.clone());
+    let path = dir.join(man.get_filename());
+    let mut buf = Vec::new();
+    man.render(&mut buf)?;
+    fs::write(&path, buf)?;
+
+    let mut paths = vec![path];
+    for sub in cmd.get_subcommands().filter(|sub| sub.get_name() != "help") {
+        let name = format!("{}-{}", cmd.get_name(), sub.get_name());
+        let mut sub = sub.clone().name(name);
+        // Subcommands have no version of their own, show rcli's in the page footer.
+        if let Some(version) = cmd.get_version() {
+            sub = sub.version(version.to_string());
+        }
+        paths.extend(render_man_pages(&sub, dir)?);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_man_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cmd = Cli::command();
+        cmd.build();
+        let paths = render_man_pages(&cmd, dir.path()).unwrap();
+        assert!(paths.contains(&dir.path().join("rcli.1")));
+        assert!(paths.contains(&dir.path().join("rcli-man.1")));
+        if cfg!(feature = "csv") {
+            let page = fs::read_to_string(dir.path().join("rcli-csv.1")).unwrap();
+            assert!(page.contains(r"\-\-delimiter"));
+            assert!(page.contains("Delimiter used in CSV file"));
+        }
+    }
+}
@@ -1,69 +1,334 @@
+#[cfg(feature = "base64")]
 mod base64;
+#[cfg(feature = "bench")]
+mod bench;
+mod clipboard;
+#[cfg(feature = "completions")]
+mod completions;
+#[cfg(feature = "compress")]
+mod compress;
+mod config;
+#[cfg(feature = "convert")]
+mod convert;
+#[cfg(feature = "csv")]
 mod csv;
+#[cfg(feature = "digest")]
+mod digest;
+#[cfg(feature = "genpass")]
 mod genpass;
+#[cfg(feature = "hex")]
+mod hex;
+#[cfg(feature = "http")]
 mod http;
+#[cfg(feature = "jsonschema")]
+mod jsonschema;
+#[cfg(feature = "jwt")]
 mod jwt;
+#[cfg(feature = "man")]
+mod man;
+#[cfg(feature = "random")]
+mod random;
+#[cfg(feature = "text")]
 mod text;
+mod update;
+#[cfg(feature = "uuid")]
+mod uuid;
+mod version;
 
-pub use self::{base64::*, csv::*, genpass::*, http::*, jwt::*, text::*};
+#[cfg(feature = "base64")]
+pub use self::base64::*;
+#[cfg(feature = "bench")]
+pub use self::bench::*;
+#[cfg(feature = "completions")]
+pub use self::completions::*;
+#[cfg(feature = "compress")]
+pub use self::compress::*;
+#[cfg(feature = "convert")]
+pub use self::convert::*;
+#[cfg(feature = "csv")]
+pub use self::csv::*;
+#[cfg(feature = "digest")]
+pub use self::digest::*;
+#[cfg(feature = "genpass")]
+pub use self::genpass::*;
+#[cfg(feature = "hex")]
+pub use self::hex::*;
+#[cfg(feature = "http")]
+pub use self::http::*;
+#[cfg(feature = "jsonschema")]
+pub use self::jsonschema::*;
+#[cfg(feature = "jwt")]
+pub use self::jwt::*;
+#[cfg(feature = "man")]
+pub use self::man::*;
+#[cfg(feature = "random")]
+pub use self::random::*;
+#[cfg(feature = "text")]
+pub use self::text::*;
+#[cfg(feature = "uuid")]
+pub use self::uuid::*;
+pub use self::{clipboard::*, update::*, version::*};
+use crate::{CmdContext, CmdOutput};
+#[cfg(feature = "jwt")]
 use chrono::Utc;
-use clap::{Parser, Subcommand};
+use clap::{ColorChoice, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use enum_dispatch::enum_dispatch;
-use std::{
-    net::IpAddr,
-    ops::RangeInclusive,
-    path::{Path, PathBuf},
-};
+use std::{ffi::OsString, fmt, path::PathBuf};
+#[cfg(feature = "http")]
+use std::{net::IpAddr, ops::RangeInclusive};
 
 #[derive(Debug, Parser)]
-#[command(name = "rcli", version, author, about, long_about = None)]
+#[command(name = "rcli", version, long_version = LONG_VERSION, author, about, long_about = None)]
 pub struct Cli {
+    /// read option defaults from this file, default: ~/.config/rcli/config.toml
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// write the output to a file instead of stdout, "-" for stdout
+    #[arg(long, default_value = "-")]
+    pub output: String,
+
+    /// the directory for files rcli names itself, like output.json or ed25519.sk
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// print results as JSON, for scripts
+    #[arg(long)]
+    pub json: bool,
+
+    /// log more to stderr, -v for info, -vv for debug, -vvv for trace
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// only log errors
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// copy the output to the clipboard instead of printing it
+    #[arg(long)]
+    pub clipboard: bool,
+
+    /// show the files that would be written, without writing them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// ask on the terminal for required arguments that are missing
+    #[arg(long)]
+    pub interactive: bool,
+
+    /// the format of the logs on stderr
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// never color the output, like setting NO_COLOR
+    #[arg(long)]
+    pub no_color: bool,
+
     #[command(subcommand)]
     pub cmd: Commands,
 }
 
+impl Cli {
+    // Like `parse`, with option defaults taken from the config file.
+    pub fn parse_with_config() -> anyhow::Result<Self> {
+        Self::try_parse_with_config(std::env::args_os()).map_err(|e| {
+            match e.downcast::<clap::Error>() {
+                // Print the usage or help, like `parse` does.
+                Ok(e) => e.exit(),
+                Err(e) => e,
+            }
+        })
+    }
+
+    // The tracing filter for the -v and -q flags.
+    pub fn log_level(&self) -> String {
+        match (self.quiet, self.verbose) {
+            (true, _) => "error".to_string(),
+            (false, 0) => match &self.cmd {
+                #[cfg(feature = "http")]
+                Commands::Http(HttpCommand::Serve(opts)) | Commands::Serve(opts) => {
+                    opts.log_level.to_string()
+                }
+                _ => "warn".to_string(),
+            },
+            (false, 1) => "info".to_string(),
+            (false, 2) => "debug".to_string(),
+            _ => "trace".to_string(),
+        }
+    }
+
+    pub fn try_parse_with_config<I, T>(args: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let config = config::Config::load(config::config_arg(&args).as_deref())?;
+        let mut cmd = config.apply(Self::command());
+        // The flag isn't parsed yet, but applies to usage errors and help too.
+        if args.iter().any(|arg| arg == "--no-color") {
+            cmd = cmd.color(ColorChoice::Never);
+        }
+        let matches = cmd.try_get_matches_from(args)?;
+        Ok(Self::from_arg_matches(&matches)?)
+    }
+}
+
 #[enum_dispatch(CmdExecutor)]
 #[derive(Debug, Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     /// Convert csv to other formats
+    #[cfg(feature = "csv")]
     #[command(name = "csv")]
     Csv(CsvOpts),
 
+    /// Convert a document between JSON, YAML and TOML
+    #[cfg(feature = "convert")]
+    #[command(name = "convert")]
+    Convert(ConvertOpts),
+
     /// Generate a random password
+    #[cfg(feature = "genpass")]
     #[command(name = "genpass")]
     GenPass(GenPassOpts),
 
+    /// Generate random ints, floats, bytes, bools or picks from a list
+    #[cfg(feature = "random")]
+    #[command(name = "random")]
+    Random(RandomOpts),
+
+    /// Generate uuids
+    #[cfg(feature = "uuid")]
+    #[command(name = "uuid")]
+    Uuid(UuidOpts),
+
+    /// Compute or check file checksums
+    #[cfg(feature = "digest")]
+    #[command(name = "digest")]
+    Digest(DigestOpts),
+
+    /// Verify files against an `<algo>:<hex>  <path>` manifest
+    #[cfg(feature = "digest")]
+    #[command(name = "verify-file")]
+    VerifyFile(VerifyFileOpts),
+
+    /// Validate a JSON or CSV document against a JSON Schema
+    #[cfg(feature = "jsonschema")]
+    #[command(name = "jsonschema")]
+    JsonSchema(JsonSchemaOpts),
+
     /// Use base64 for encoding or decoding
+    #[cfg(feature = "base64")]
     #[command(subcommand, name = "base64")]
     Base64(Base64Command),
 
+    /// Compress a file with gzip, zstd or xz
+    #[cfg(feature = "compress")]
+    #[command(name = "compress")]
+    Compress(CompressOpts),
+
+    /// Decompress a gzip, zstd or xz file
+    #[cfg(feature = "compress")]
+    #[command(name = "decompress")]
+    Decompress(DecompressOpts),
+
+    /// Use hex for encoding or decoding
+    #[cfg(feature = "hex")]
+    #[command(subcommand, name = "hex")]
+    Hex(HexCommand),
+
     /// Text signing or signature verification.
+    #[cfg(feature = "text")]
     #[command(subcommand, name = "text")]
     Text(TextCommand),
 
     /// Start a simple file http server
+    #[cfg(feature = "http")]
     #[command(subcommand, name = "http")]
     Http(HttpCommand),
 
+    /// Serve files over http, like `http serve`
+    #[cfg(feature = "http")]
+    #[command(name = "serve")]
+    Serve(HttpServerOpts),
+
     /// jwt sign or verify
+    #[cfg(feature = "jwt")]
     #[command(subcommand, name = "jwt")]
     Jwt(JwtCommand),
+
+    /// Print the text on the clipboard
+    #[command(name = "clipboard")]
+    Clipboard(ClipboardOpts),
+
+    /// Time an operation to compare its performance
+    #[cfg(feature = "bench")]
+    #[command(name = "bench")]
+    Bench(BenchOpts),
+
+    /// Update rcli to the latest GitHub release
+    #[command(name = "self-update")]
+    SelfUpdate(SelfUpdateOpts),
+
+    /// Print the version, --verbose for the build details
+    #[command(name = "version")]
+    Version(VersionOpts),
+
+    /// Generate a shell completion script, or source `COMPLETE=bash rcli` for dynamic ones
+    #[cfg(feature = "completions")]
+    #[command(name = "completions")]
+    Completions(CompletionsOpts),
+
+    /// Generate man pages for rcli and all its subcommands
+    #[cfg(feature = "man")]
+    #[command(name = "man")]
+    Man(ManOpts),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// human-readable lines
+    Text,
+
+    /// one JSON object per line, for log aggregators
+    Json,
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
 }
 
 #[allow(async_fn_in_trait)]
 #[enum_dispatch]
 pub trait CmdExecutor {
-    async fn execute(self) -> anyhow::Result<()>;
+    async fn execute(self, ctx: &mut CmdContext) -> anyhow::Result<CmdOutput>;
 }
 
+#[cfg(any(
+    feature = "base64",
+    feature = "compress",
+    feature = "convert",
+    feature = "csv",
+    feature = "digest",
+    feature = "hex",
+    feature = "jsonschema",
+    feature = "text"
+))]
 fn validate_file(filename: &str) -> Result<String, String> {
-    if filename == "-" || Path::new(filename).exists() {
+    if filename == "-" || crate::is_url(filename) || std::path::Path::new(filename).exists() {
         Ok(filename.to_string())
     } else {
         Err(format!("File not found: {}", filename))
     }
 }
 
+#[cfg(any(feature = "jwt", feature = "man", feature = "text"))]
 fn validate_path(path: &str) -> Result<PathBuf, String> {
     let p = PathBuf::from(path);
     if p.exists() && p.is_dir() {
@@ -73,8 +338,20 @@ fn validate_path(path: &str) -> Result<PathBuf, String> {
     }
 }
 
+#[cfg(feature = "http")]
+fn validate_file_or_dir(path: &str) -> Result<PathBuf, String> {
+    let p = PathBuf::from(path);
+    if p.exists() {
+        Ok(p)
+    } else {
+        Err(format!("Path not found: {}", path))
+    }
+}
+
+#[cfg(feature = "http")]
 const PORT_RANGE: RangeInclusive<usize> = 1..=65535;
 
+#[cfg(feature = "http")]
 fn validate_port(s: &str) -> Result<u16, String> {
     let port: usize = s
         .parse()
@@ -90,51 +367,275 @@ fn validate_port(s: &str) -> Result<u16, String> {
     }
 }
 
+#[cfg(feature = "http")]
 fn validate_addr(s: &str) -> Result<IpAddr, String> {
     s.parse()
         .map_err(|_| format!("`{}` isn't a valid IP address", s))
 }
 
+#[cfg(any(feature = "bench", feature = "http"))]
+fn validate_size(s: &str) -> Result<usize, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number = number
+        .parse::<usize>()
+        .map_err(|_| format!("`{}` isn't a valid size", s))?;
+    let multiplier = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        _ => return Err(format!("`{}` isn't a valid size unit,[B, KB, MB, GB]", s)),
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("`{}` is too large", s))
+}
+
+// A timestamp relative to now, in the past with a leading `-` like -1h.
+#[cfg(feature = "jwt")]
 fn validate_exp_time(s: &str) -> Result<u64, String> {
-    let mut parts = s.chars().peekable();
-    let mut duration_str = String::new();
-    while let Some(&ch) = parts.peek() {
-        if ch.is_ascii_digit() {
-            duration_str.push(ch);
-            parts.next();
-        } else {
-            break;
-        }
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("the time can't be empty".to_string());
     }
-    let duration = duration_str
-        .parse::<i64>()
-        .map_err(|_| format!("`{}` isn't a valid numbers", s))?;
-    let unit = parts
-        .take_while(|c| c.is_ascii_alphabetic())
-        .collect::<String>();
-    let seconds = match unit.as_str() {
-        "s" => duration,
-        "m" => duration * 60,
-        "h" => duration * 3600,
-        "d" => duration * 86400,
-        "w" => duration * 604800,
-        "M" => duration * 2592000,
-        "y" => duration * 31536000,
-        _ => {
-            return Err(format!(
-                "`{}` isn't a valid time unit,[s, m, h, d, w, M, y]",
-                s
-            ))
-        }
+    let (past, duration) = match s.strip_prefix('-') {
+        Some(duration) => (true, duration),
+        None => (false, s),
     };
-    let timestamp = Utc::now().timestamp() + seconds;
-    Ok(timestamp as u64)
+    let seconds = i64::try_from(validate_duration(duration)?)
+        .map_err(|_| format!("`{}` is too far from now", s))?;
+    let now = Utc::now().timestamp();
+    let timestamp = match past {
+        true => now.checked_sub(seconds),
+        false => now.checked_add(seconds),
+    };
+    timestamp
+        .and_then(|timestamp| u64::try_from(timestamp).ok())
+        .ok_or_else(|| format!("`{}` is too far from now", s))
 }
 
+// Seconds in a duration like: 30s, 5m, 1d, or a sum of them like 1h30m
+#[cfg(feature = "jwt")]
+fn validate_duration(s: &str) -> Result<u64, String> {
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(format!("`{}` isn't a valid numbers", s));
+    }
+    let mut seconds: u64 = 0;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(split);
+        let duration = number
+            .parse::<u64>()
+            .map_err(|_| format!("`{}` isn't a valid numbers", s))?;
+        let unit = tail.chars().next().unwrap_or_default();
+        let multiplier = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 604800,
+            'M' => 2592000,
+            'y' => 31536000,
+            _ => {
+                return Err(format!(
+                    "`{}` isn't a valid time unit,[s, m, h, d, w, M, y]",
+                    s
+                ))
+            }
+        };
+        seconds = duration
+            .checked_mul(multiplier)
+            .and_then(|d| seconds.checked_add(d))
+            .ok_or_else(|| format!("`{}` is too long", s))?;
+        rest = &tail[unit.len_utf8()..];
+    }
+    Ok(seconds)
+}
+
+#[cfg(any(
+    feature = "base64",
+    feature = "compress",
+    feature = "convert",
+    feature = "csv",
+    feature = "digest",
+    feature = "hex",
+    feature = "jsonschema",
+    feature = "text"
+))]
+#[test]
+fn test_validate_file() {
+    assert_eq!(validate_file("-").unwrap(), "-");
+    assert_eq!(validate_file("Cargo.toml").unwrap(), "Cargo.toml");
+    assert!(validate_file("not-found.csv").is_err());
+}
+
+#[cfg(any(feature = "bench", feature = "http"))]
+#[test]
+fn test_validate_size() {
+    assert_eq!(validate_size("100").unwrap(), 100);
+    assert_eq!(validate_size("512KB").unwrap(), 512 * 1024);
+    assert_eq!(validate_size("10MB").unwrap(), 10 * 1024 * 1024);
+    assert_eq!(validate_size("1g").unwrap(), 1024 * 1024 * 1024);
+    assert!(validate_size("10XB").is_err());
+    assert!(validate_size("MB").is_err());
+}
+
+#[cfg(feature = "jwt")]
+#[test]
+fn test_validate_duration() {
+    assert_eq!(validate_duration("30s").unwrap(), 30);
+    assert_eq!(validate_duration("1m").unwrap(), 60);
+    assert_eq!(validate_duration("2d").unwrap(), 2 * 86400);
+    assert!(validate_duration("1x").is_err());
+    assert!(validate_duration("m").is_err());
+    assert_eq!(validate_duration("1h30m").unwrap(), 3600 + 30 * 60);
+    assert_eq!(validate_duration("2d12h").unwrap(), 2 * 86400 + 12 * 3600);
+    assert!(validate_duration("1h30m!").is_err());
+    assert!(validate_duration("1h30").is_err());
+    assert!(validate_duration("").is_err());
+}
+
+#[cfg(feature = "jwt")]
 #[test]
 fn test_validate_exp_time() {
     assert_eq!(
         validate_exp_time("12s").unwrap(),
         (Utc::now().timestamp() + 12) as u64
     );
+    assert_eq!(
+        validate_exp_time("1h30m").unwrap(),
+        (Utc::now().timestamp() + 5400) as u64
+    );
+    assert!(validate_exp_time("1h30mx").is_err());
+    assert_eq!(
+        validate_exp_time("-1h").unwrap(),
+        (Utc::now().timestamp() - 3600) as u64
+    );
+    assert!(validate_exp_time("-100y").is_err());
+    assert!(validate_exp_time("99999999999999999y").is_err());
+    assert!(validate_exp_time("300000000000y").is_err());
+    assert!(validate_exp_time("").is_err());
+    assert!(validate_exp_time("-").is_err());
+}
+
+// Run rcli writing to a temp `--output`, and return what was written.
+#[cfg(test)]
+pub(crate) async fn run_with_output(args: &[&str]) -> (String, anyhow::Result<()>) {
+    use std::io::Write;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("output");
+    let mut argv = vec!["rcli", "--output", path.to_str().unwrap()];
+    argv.extend(args);
+    let cli = Cli::try_parse_from(argv).unwrap();
+    let mut output = crate::Output::new(&cli.output, cli.json).unwrap();
+    let mut ctx = CmdContext::default().with_output_dir(cli.output_dir);
+    let result = cli
+        .cmd
+        .execute(&mut ctx)
+        .await
+        .and_then(|result| output.print(result));
+    output.flush().unwrap();
+    let written = std::fs::read(&path).unwrap();
+    (String::from_utf8_lossy(&written).to_string(), result)
+}
+
+#[cfg(all(feature = "genpass", feature = "http"))]
+#[test]
+fn test_log_level() {
+    let level = |args: &[&str]| Cli::try_parse_from(args).unwrap().log_level();
+    assert_eq!(level(&["rcli", "-vv", "genpass"]), "debug");
+    assert_eq!(level(&["rcli", "-v", "genpass"]), "info");
+    assert_eq!(level(&["rcli", "-vvvv", "genpass"]), "trace");
+    assert_eq!(level(&["rcli", "-q", "genpass"]), "error");
+    assert_eq!(level(&["rcli", "genpass"]), "warn");
+    assert_eq!(level(&["rcli", "http", "serve", "--path", "."]), "info");
+    assert_eq!(level(&["rcli", "serve"]), "info");
+    assert_eq!(
+        level(&[
+            "rcli",
+            "-v",
+            "http",
+            "serve",
+            "--path",
+            ".",
+            "--log-level",
+            "warn"
+        ]),
+        "info"
+    );
+    assert!(Cli::try_parse_from(["rcli", "-q", "-v", "genpass"]).is_err());
+}
+
+#[cfg(feature = "genpass")]
+#[test]
+fn test_config_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[genpass]\nlength = 24\nsymbol = false\n").unwrap();
+    let config = path.to_str().unwrap();
+
+    let cli = Cli::try_parse_with_config(["rcli", "--config", config, "genpass"]).unwrap();
+    let Commands::GenPass(opts) = cli.cmd else {
+        panic!("expected genpass");
+    };
+    assert_eq!(opts.length, 24);
+    assert!(!opts.symbol);
+    assert!(opts.lower);
+
+    let args = ["rcli", "--config", config, "genpass", "--length", "8"];
+    let Commands::GenPass(opts) = Cli::try_parse_with_config(args).unwrap().cmd else {
+        panic!("expected genpass");
+    };
+    assert_eq!(opts.length, 8);
+
+    std::fs::write(&path, "output_dir = \"out\"\n").unwrap();
+    let cli = Cli::try_parse_with_config(["rcli", "--config", config, "genpass"]).unwrap();
+    assert_eq!(cli.output_dir, Some(PathBuf::from("out")));
+
+    std::fs::write(&path, "[genpass]\nsize = 24\n").unwrap();
+    assert!(Cli::try_parse_with_config(["rcli", "--config", config, "genpass"]).is_err());
+}
+
+#[cfg(all(feature = "base64", feature = "digest", feature = "uuid"))]
+#[tokio::test]
+async fn test_execute_returns_output() {
+    let execute = |args: &'static [&'static str]| async move {
+        let cli = Cli::try_parse_from(args).unwrap();
+        cli.cmd.execute(&mut CmdContext::default()).await.unwrap()
+    };
+
+    let args = &["rcli", "base64", "encode", "-i", "assets/decode.b64"];
+    let CmdOutput::Json { json, lines } = execute(args).await else {
+        panic!("base64 encode should return JSON");
+    };
+    assert_eq!(
+        json["result"],
+        "VGhpcyBpcyBhIGJhc2U2NCBlbmNvZGluZyB0ZXh0Lgo="
+    );
+    assert_eq!(lines, ["VGhpcyBpcyBhIGJhc2U2NCBlbmNvZGluZyB0ZXh0Lgo="]);
+
+    let CmdOutput::Json { json, lines } = execute(&["rcli", "uuid", "--count", "3"]).await else {
+        panic!("uuid should return JSON");
+    };
+    assert_eq!(json["uuids"].as_array().unwrap().len(), 3);
+    assert!(lines.iter().all(|uuid| uuid.len() == 36));
+
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let manifest = format!("sha256:{}  assets/juventus.csv\n", "0".repeat(64));
+    std::fs::write(file.path(), manifest).unwrap();
+    let args = ["rcli", "verify-file", "-m", file.path().to_str().unwrap()];
+    let cli = Cli::try_parse_from(args).unwrap();
+    let result = cli.cmd.execute(&mut CmdContext::default()).await.unwrap();
+    let CmdOutput::Failed { output, error } = result else {
+        panic!("a failed check should return the results and the error");
+    };
+    assert_eq!(crate::exit_code(&error), 3);
+    assert!(
+        matches!(*output, CmdOutput::Json { ref lines, .. } if lines == &["assets/juventus.csv: FAIL"])
+    );
 }
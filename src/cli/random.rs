@@ -0,0 +1,91 @@
+use super::CmdExecutor;
+use crate::{process_random, CmdContext, CmdOutput};
+use clap::{Args, ValueEnum};
+use serde_json::json;
+use std::fmt;
+
+#[derive(Debug, Args)]
+pub struct RandomOpts {
+    /// the kind of value, default: choice with --choice, else int
+    #[arg(short = 't', long = "type", value_enum)]
+    pub kind: Option<RandomType>,
+
+    /// the smallest int or float
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub min: f64,
+
+    /// the largest int or float
+    #[arg(long, default_value_t = 100.0, allow_negative_numbers = true)]
+    pub max: f64,
+
+    /// how many bytes to print as hex
+    #[arg(long, default_value_t = 16)]
+    pub len: usize,
+
+    /// the values to pick from, like: a,b,c
+    #[arg(long, value_delimiter = ',')]
+    pub choice: Vec<String>,
+
+    /// how many values to generate
+    #[arg(short, long, default_value_t = 1)]
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum RandomType {
+    /// a whole number between --min and --max
+    Int,
+
+    /// a decimal number between --min and --max
+    Float,
+
+    /// --len random bytes, hex encoded
+    Bytes,
+
+    /// true or false
+    Bool,
+
+    /// one of the --choice values
+    Choice,
+}
+
+impl CmdExecutor for RandomOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let kind = match self.kind {
+            Some(kind) => kind,
+            None if !self.choice.is_empty() => RandomType::Choice,
+            None => RandomType::Int,
+        };
+        let values = (0..self.count)
+            .map(|_| {
+                process_random(
+                    &kind.to_string(),
+                    self.min,
+                    self.max,
+                    self.len,
+                    &self.choice,
+                )
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(CmdOutput::Json {
+            json: json!({
+                "command": "random",
+                "type": kind.to_string(),
+                "values": values,
+            }),
+            lines: values,
+        })
+    }
+}
+
+impl fmt::Display for RandomType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RandomType::Int => write!(f, "int"),
+            RandomType::Float => write!(f, "float"),
+            RandomType::Bytes => write!(f, "bytes"),
+            RandomType::Bool => write!(f, "bool"),
+            RandomType::Choice => write!(f, "choice"),
+        }
+    }
+}
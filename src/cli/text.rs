@@ -1,13 +1,15 @@
 use super::{validate_file, validate_path, CmdExecutor};
 use crate::{
-    get_reader, process_text_decrypt, process_text_encrypt, process_text_generate_key,
-    process_text_sign, process_text_verify, read_contents, URL_SAFE_ENGINE,
+    check_stdin_input, get_reader, process_text_decrypt, process_text_encrypt,
+    process_text_generate_key, process_text_keyinfo, process_text_sign, process_text_verify,
+    read_contents, read_secret, CliError, CmdContext, CmdOutput, URL_SAFE_ENGINE,
 };
 use anyhow::{anyhow, Result};
 use base64::Engine;
-use clap::{Args, Subcommand, ValueEnum};
+use clap::{Args, Subcommand, ValueEnum, ValueHint};
 use enum_dispatch::enum_dispatch;
-use std::{fmt, fs, path::PathBuf};
+use serde_json::json;
+use std::{fmt, path::PathBuf};
 
 #[enum_dispatch(CmdExecutor)]
 #[derive(Debug, Subcommand)]
@@ -31,17 +33,21 @@ pub enum TextCommand {
     /// Decrypt a message with a key file
     #[command(name = "decrypt")]
     Decrypt(TextDecryptOpts),
+
+    /// Show the type of a key file, and the public key of an ed25519 one
+    #[command(name = "keyinfo")]
+    KeyInfo(TextKeyInfoOpts),
 }
 
 #[derive(Debug, Args)]
 pub struct TextSignOpts {
     /// a message to signing, from file or stdin
-    #[arg(short, long, value_parser = validate_file, default_value = "-")]
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
     pub message: String,
 
-    /// the sign key file, like: secret key
-    #[arg(short, long, value_parser = validate_file)]
-    pub key: String,
+    /// the sign key file, like: secret key, else read from RCLI_TEXT_KEY
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath)]
+    pub key: Option<String>,
 
     /// the signature format
     #[arg(long, value_enum, default_value = "blake3")]
@@ -51,18 +57,18 @@ pub struct TextSignOpts {
 #[derive(Debug, Args)]
 pub struct TextVerifyOpts {
     /// a message to be verified, from file or stdin
-    #[arg(short, long, value_parser = validate_file, default_value = "-")]
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
     pub message: String,
 
-    /// the verify key file, like: public key
-    #[arg(short, long, value_parser = validate_file)]
-    pub key: String,
+    /// the verify key file, like: public key, else read from RCLI_TEXT_KEY
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath)]
+    pub key: Option<String>,
 
     /// the signature format
     #[arg(long, value_enum, default_value = "blake3")]
     pub format: SignFormat,
 
-    /// the signature
+    /// the signature, base64url encoded as `text sign` prints it
     #[arg(short, long)]
     pub signature: String,
 }
@@ -73,9 +79,9 @@ pub struct TextGenerateKeyOpts {
     #[arg(long, value_enum, default_value = "blake3")]
     pub format: SignFormat,
 
-    /// save the key to a dir
-    #[arg(short, long, value_parser = validate_path)]
-    pub output: PathBuf,
+    /// save the key to a dir, default: --output-dir or the current dir
+    #[arg(short, long, value_parser = validate_path, value_hint = ValueHint::DirPath)]
+    pub output: Option<PathBuf>,
 }
 
 #[derive(Debug, ValueEnum, Clone, Copy)]
@@ -90,12 +96,12 @@ pub enum SignFormat {
 #[derive(Debug, Args)]
 pub struct TextEncryptOpts {
     /// a message to encrypt, from file or stdin
-    #[arg(short, long, value_parser = validate_file, default_value = "-")]
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
     pub message: String,
 
-    /// the encrypt key file
-    #[arg(short, long, value_parser = validate_file)]
-    pub key: String,
+    /// the encrypt key file, else read from RCLI_TEXT_KEY
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath)]
+    pub key: Option<String>,
 
     /// the cipher kind
     #[arg(long, value_enum, default_value = "chacha20-poly1305")]
@@ -105,18 +111,25 @@ pub struct TextEncryptOpts {
 #[derive(Debug, Args)]
 pub struct TextDecryptOpts {
     /// a message to decrypt, from file or stdin
-    #[arg(short, long, value_parser = validate_file, default_value = "-")]
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath, default_value = "-")]
     pub message: String,
 
-    /// the decrypt key file
-    #[arg(short, long, value_parser = validate_file)]
-    pub key: String,
+    /// the decrypt key file, else read from RCLI_TEXT_KEY
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath)]
+    pub key: Option<String>,
 
     /// the cipher kind
     #[arg(long, value_enum, default_value = "chacha20-poly1305")]
     pub cipher: CipherKind,
 }
 
+#[derive(Debug, Args)]
+pub struct TextKeyInfoOpts {
+    /// the key file to inspect
+    #[arg(short, long, value_parser = validate_file, value_hint = ValueHint::FilePath)]
+    pub key: String,
+}
+
 #[derive(Debug, ValueEnum, Clone, Copy)]
 pub enum CipherKind {
     /// chacha20poly1305 algorithm
@@ -124,70 +137,117 @@ pub enum CipherKind {
 }
 
 impl CmdExecutor for TextSignOpts {
-    async fn execute(self) -> Result<()> {
+    async fn execute(self, _ctx: &mut CmdContext) -> Result<CmdOutput> {
+        check_stdin_input(&self.message, "--message <file>")?;
         let mut message = get_reader(&self.message)?;
-        let key = read_contents(&self.key)?;
+        let key = read_key(self.key.as_deref())?;
         let signature = process_text_sign(&mut message, &key, &self.format.to_string())?;
-        let encoded = URL_SAFE_ENGINE.encode(signature);
-        println!("{}", encoded);
-        Ok(())
+        Ok(CmdOutput::Text(URL_SAFE_ENGINE.encode(signature)))
     }
 }
 
 impl CmdExecutor for TextVerifyOpts {
-    async fn execute(self) -> Result<()> {
+    async fn execute(self, _ctx: &mut CmdContext) -> Result<CmdOutput> {
         let mut message = get_reader(&self.message)?;
-        let key = read_contents(&self.key)?;
-        let result = process_text_verify(
-            &mut message,
-            &key,
-            &self.format.to_string(),
-            self.signature.as_bytes(),
-        )?;
-        println!("{}", result);
-        Ok(())
+        let key = read_key(self.key.as_deref())?;
+        // `text sign` prints the signature base64url encoded.
+        let signature = URL_SAFE_ENGINE.decode(self.signature.trim())?;
+        let result = process_text_verify(&mut message, &key, &self.format.to_string(), &signature)?;
+        let output = CmdOutput::Text(result.to_string());
+        if !result {
+            return Ok(CmdOutput::Failed {
+                output: Box::new(output),
+                error: CliError::Verification("Signature verification failed".to_string()).into(),
+            });
+        }
+        Ok(output)
     }
 }
 
 impl CmdExecutor for TextGenerateKeyOpts {
-    async fn execute(self) -> Result<()> {
+    async fn execute(self, ctx: &mut CmdContext) -> Result<CmdOutput> {
         let key = process_text_generate_key(&self.format.to_string())?;
-        let path = self.output;
+        let path = |ctx: &CmdContext, name: &str| match &self.output {
+            Some(dir) => Ok(dir.join(name)),
+            None => ctx.output_path(name),
+        };
         match self.format {
             SignFormat::Blake3 => {
-                fs::write(path.join("blake3.txt"), key[0])?;
+                let path = path(ctx, "blake3.txt")?;
+                ctx.write_file(path, key[0])?;
             }
             SignFormat::Ed25519 => {
-                fs::write(path.join("ed25519.sk"), key[0])?;
-                fs::write(path.join("ed25519.pk"), key[1])?;
+                let (sk, pk) = (path(ctx, "ed25519.sk")?, path(ctx, "ed25519.pk")?);
+                ctx.write_file(sk, key[0])?;
+                ctx.write_file(pk, key[1])?;
             }
         }
-        Ok(())
+        Ok(CmdOutput::None)
     }
 }
 
 impl CmdExecutor for TextEncryptOpts {
-    async fn execute(self) -> Result<()> {
+    async fn execute(self, _ctx: &mut CmdContext) -> Result<CmdOutput> {
         let message = read_contents(&self.message)?;
-        let key = read_contents(&self.key)?;
+        let key = read_key(self.key.as_deref())?;
         let encrypted = process_text_encrypt(&message, &key, &self.cipher.to_string())?;
-        let encoded = URL_SAFE_ENGINE.encode(encrypted);
-        println!("{}", encoded);
-        Ok(())
+        Ok(CmdOutput::Text(URL_SAFE_ENGINE.encode(encrypted)))
     }
 }
 
 impl CmdExecutor for TextDecryptOpts {
-    async fn execute(self) -> Result<()> {
+    async fn execute(self, _ctx: &mut CmdContext) -> Result<CmdOutput> {
         let message = read_contents(&self.message)?;
         let decode = URL_SAFE_ENGINE.decode(message).map_err(|e| {
             anyhow!("base64 decode error: {e} perhaps you could check the file for line breaks.")
         })?;
-        let key = read_contents(&self.key)?;
+        let key = read_key(self.key.as_deref())?;
         let decrypted = process_text_decrypt(&decode, &key, &self.cipher.to_string())?;
-        let plaintext = String::from_utf8(decrypted)?;
-        println!("{}", plaintext);
-        Ok(())
+        Ok(CmdOutput::Text(String::from_utf8(decrypted)?))
+    }
+}
+
+impl CmdExecutor for TextKeyInfoOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> Result<CmdOutput> {
+        let key = read_contents(&self.key)?;
+        let info = process_text_keyinfo(&key, &self.key)?;
+        let mut lines = vec![
+            format!("type: {}", info.kind),
+            format!("length: {} bytes", info.len),
+        ];
+        if let Some(public_key) = &info.public_key {
+            lines.push(format!("public key: {}", public_key));
+        }
+        Ok(CmdOutput::Json {
+            json: json!({
+                "command": "text keyinfo",
+                "kind": info.kind,
+                "len": info.len,
+                "public_key": info.public_key,
+            }),
+            lines,
+        })
+    }
+}
+
+const KEY_ENV: &str = "RCLI_TEXT_KEY";
+
+fn read_key(key: Option<&str>) -> Result<Vec<u8>> {
+    read_key_with(key, std::env::var(KEY_ENV).ok())
+}
+
+// RCLI_TEXT_KEY, given as `env`, or the key typed at the prompt, is a 32 byte
+// key as is, or base64url encoded for binary keys like ed25519's.
+fn read_key_with(key: Option<&str>, env: Option<String>) -> Result<Vec<u8>> {
+    if let Some(key) = key {
+        return read_contents(key);
+    }
+    match read_secret(env, "Text key: ")? {
+        Some(key) if key.len() == 32 => Ok(key.into_bytes()),
+        Some(key) => URL_SAFE_ENGINE
+            .decode(key.trim())
+            .map_err(|_| anyhow!("{} isn't a 32 byte key or its base64", KEY_ENV)),
+        None => Err(anyhow!("either --key or {} is required", KEY_ENV)),
     }
 }
 
@@ -207,3 +267,47 @@ impl fmt::Display for CipherKind {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_with_output;
+    use std::fs;
+
+    #[test]
+    fn test_key_from_env() {
+        let env = |key: &str| Some(key.to_string());
+        let key = read_key_with(None, env("0123456789abcdefghijklmnopqrstuv")).unwrap();
+        assert_eq!(key, b"0123456789abcdefghijklmnopqrstuv");
+        let signature = process_text_sign(&mut &b"hello"[..], &key, "blake3").unwrap();
+        assert!(process_text_verify(&mut &b"hello"[..], &key, "blake3", &signature).unwrap());
+
+        let keys = process_text_generate_key("ed25519").unwrap();
+        let signing = read_key_with(None, env(&URL_SAFE_ENGINE.encode(keys[0]))).unwrap();
+        let verifying = read_key_with(None, env(&URL_SAFE_ENGINE.encode(keys[1]))).unwrap();
+        assert_eq!(signing, keys[0]);
+        let signature = process_text_sign(&mut &b"hello"[..], &signing, "ed25519").unwrap();
+        assert!(
+            process_text_verify(&mut &b"hello"[..], &verifying, "ed25519", &signature).unwrap()
+        );
+        assert!(read_key_with(None, env("not a key")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gen_key_in_output_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("keys");
+        let args = [
+            "--output-dir",
+            out.to_str().unwrap(),
+            "text",
+            "gen",
+            "--format",
+            "ed25519",
+        ];
+        let (_, result) = run_with_output(&args).await;
+        result.unwrap();
+        assert_eq!(fs::read(out.join("ed25519.sk")).unwrap().len(), 32);
+        assert_eq!(fs::read(out.join("ed25519.pk")).unwrap().len(), 32);
+    }
+}
@@ -0,0 +1,60 @@
+use super::CmdExecutor;
+use crate::{CmdContext, CmdOutput};
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct SelfUpdateOpts {
+    /// only report whether a newer release exists
+    #[arg(long)]
+    pub check_only: bool,
+}
+
+#[cfg(feature = "update")]
+impl CmdExecutor for SelfUpdateOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        use crate::{process_self_update, process_update_check, GITHUB_API};
+        use serde_json::json;
+
+        // The GitHub client is blocking, keep it off the runtime threads.
+        let check_only = self.check_only;
+        let (check, updated) = tokio::task::spawn_blocking(move || {
+            let check = process_update_check(GITHUB_API, env!("CARGO_PKG_VERSION"))?;
+            let updated = check.newer && !check_only;
+            if updated {
+                process_self_update(&check)?;
+            }
+            anyhow::Ok((check, updated))
+        })
+        .await??;
+
+        let line = if updated {
+            format!("Updated rcli from {} to {}", check.current, check.latest)
+        } else if check.newer {
+            format!(
+                "rcli {} is available, this is {}",
+                check.latest, check.current
+            )
+        } else {
+            format!("rcli {} is up to date", check.current)
+        };
+        Ok(CmdOutput::Json {
+            json: json!({
+                "command": "self-update",
+                "current": check.current,
+                "latest": check.latest,
+                "newer": check.newer,
+                "updated": updated,
+            }),
+            lines: vec![line],
+        })
+    }
+}
+
+#[cfg(not(feature = "update"))]
+impl CmdExecutor for SelfUpdateOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        Err(anyhow::anyhow!(
+            "Can't update, rcli was built without the `update` feature"
+        ))
+    }
+}
@@ -0,0 +1,92 @@
+use super::CmdExecutor;
+use crate::{process_gen_uuid, CmdContext, CmdOutput};
+use clap::{Args, ValueEnum};
+use serde_json::json;
+use std::fmt;
+
+#[derive(Debug, Args)]
+pub struct UuidOpts {
+    /// the uuid version
+    #[arg(long, value_enum, default_value = "v4")]
+    pub version: UuidVersion,
+
+    /// how many uuids to generate
+    #[arg(short, long, default_value_t = 1)]
+    pub count: u32,
+
+    /// print uppercase hex digits
+    #[arg(short, long)]
+    pub uppercase: bool,
+
+    /// print without hyphens
+    #[arg(long)]
+    pub no_hyphens: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum UuidVersion {
+    /// time and node based
+    #[value(name = "v1", alias = "1")]
+    V1,
+
+    /// random
+    #[value(name = "v4", alias = "4")]
+    V4,
+
+    /// time ordered and random
+    #[value(name = "v7", alias = "7")]
+    V7,
+}
+
+impl CmdExecutor for UuidOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let mut uuids = Vec::with_capacity(self.count as usize);
+        for _ in 0..self.count {
+            let uuid = process_gen_uuid(&self.version.to_string())?;
+            let mut uuid = if self.no_hyphens {
+                uuid.simple().to_string()
+            } else {
+                uuid.hyphenated().to_string()
+            };
+            if self.uppercase {
+                uuid.make_ascii_uppercase();
+            }
+            uuids.push(uuid);
+        }
+        Ok(CmdOutput::Json {
+            json: json!({
+                "command": "uuid",
+                "uuids": uuids,
+            }),
+            lines: uuids,
+        })
+    }
+}
+
+impl fmt::Display for UuidVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UuidVersion::V1 => write!(f, "v1"),
+            UuidVersion::V4 => write!(f, "v4"),
+            UuidVersion::V7 => write!(f, "v7"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::run_with_output;
+
+    #[tokio::test]
+    async fn test_uuid_count_and_format() {
+        let args = ["uuid", "--version", "7", "-c", "3", "-u", "--no-hyphens"];
+        let (output, result) = run_with_output(&args).await;
+        result.unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        for line in lines {
+            assert_eq!(line.len(), 32);
+            assert!(!line.chars().any(|c| c.is_ascii_lowercase()));
+        }
+    }
+}
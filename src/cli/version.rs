@@ -0,0 +1,59 @@
+use super::CmdExecutor;
+use crate::{CmdContext, CmdOutput};
+use clap::Args;
+use serde_json::json;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The version with the commit, build date and compiler, for `--version`.
+pub const LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    "\ncommit: ",
+    env!("RCLI_GIT_COMMIT"),
+    "\nbuilt: ",
+    env!("RCLI_BUILD_DATE"),
+    "\nrustc: ",
+    env!("RCLI_RUSTC_VERSION"),
+);
+
+#[derive(Debug, Args)]
+pub struct VersionOpts {
+    /// also print the commit, build date and compiler
+    #[arg(long)]
+    pub verbose: bool,
+}
+
+impl CmdExecutor for VersionOpts {
+    async fn execute(self, _ctx: &mut CmdContext) -> anyhow::Result<CmdOutput> {
+        let version = if self.verbose { LONG_VERSION } else { VERSION };
+        Ok(CmdOutput::Json {
+            json: json!({
+                "command": "version",
+                "version": VERSION,
+                "commit": env!("RCLI_GIT_COMMIT"),
+                "build_date": env!("RCLI_BUILD_DATE"),
+                "rustc": env!("RCLI_RUSTC_VERSION"),
+            }),
+            lines: vec![format!("rcli {}", version)],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_with_output;
+
+    #[tokio::test]
+    async fn test_version_output() {
+        let (output, result) = run_with_output(&["version"]).await;
+        result.unwrap();
+        assert_eq!(output, format!("rcli {}\n", VERSION));
+
+        let (output, result) = run_with_output(&["version", "--verbose"]).await;
+        result.unwrap();
+        assert!(output.starts_with(&format!("rcli {}\n", VERSION)));
+        assert!(output.contains("commit: "));
+        assert!(output.contains("rustc: "));
+    }
+}
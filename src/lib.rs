@@ -2,6 +2,8 @@ mod cli;
 mod process;
 mod utils;
 
-pub use cli::{Cli, CmdExecutor};
+#[cfg(feature = "completions")]
+pub use cli::complete_dynamic;
+pub use cli::{Cli, CmdExecutor, Commands, LogFormat};
 pub use process::*;
 pub use utils::*;
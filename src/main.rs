@@ -1,9 +1,39 @@
-use clap::Parser;
-use rcli::{Cli, CmdExecutor};
+#[cfg(feature = "completions")]
+use rcli::complete_dynamic;
+use rcli::{
+    disable_color, exit_code, init_tracing, render_error, should_color, Cli, CmdContext,
+    CmdExecutor, LogFormat, Output,
+};
+use std::process;
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
-    cli.cmd.execute().await?;
+async fn main() {
+    #[cfg(feature = "completions")]
+    complete_dynamic();
+    if let Err(e) = run().await {
+        eprintln!("{}", render_error(&e, should_color(&std::io::stderr())));
+        process::exit(exit_code(&e));
+    }
+}
+
+async fn run() -> anyhow::Result<()> {
+    let cli = Cli::parse_with_config()?;
+    if cli.no_color {
+        disable_color();
+    }
+    init_tracing(&cli.log_level(), cli.log_format == LogFormat::Json)?;
+    let output = if cli.dry_run {
+        Output::dry_run(&cli.output, cli.json)?
+    } else {
+        Output::new(&cli.output, cli.json)?
+    };
+    let mut output = output.with_clipboard(cli.clipboard);
+    let mut ctx = CmdContext::default()
+        .with_dry_run(cli.dry_run)
+        .with_output_dir(cli.output_dir)
+        .with_interactive(cli.interactive);
+    let result = cli.cmd.execute(&mut ctx).await?;
+    output.print(result)?;
+    output.finish(&ctx.take_skipped())?;
     Ok(())
 }
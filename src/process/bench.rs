@@ -0,0 +1,122 @@
+#[cfg(feature = "text")]
+use crate::process_text_encrypt;
+use crate::{process_decode, process_digest, process_encode, process_hex_encode, STANDARD_ENGINE};
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use rand::{Rng, RngCore};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug)]
+pub struct BenchReport {
+    pub iterations: u32,
+    pub size: usize,
+    pub total: Duration,
+    // Sorted from the fastest run to the slowest.
+    latencies: Vec<Duration>,
+}
+
+impl BenchReport {
+    pub fn ops_per_sec(&self) -> f64 {
+        self.iterations as f64 / self.total.as_secs_f64()
+    }
+
+    pub fn bytes_per_sec(&self) -> f64 {
+        self.ops_per_sec() * self.size as f64
+    }
+
+    /// The latency at `p` in 0..=1, so 0.5 is the median.
+    pub fn percentile(&self, p: f64) -> Duration {
+        let idx = ((self.latencies.len() - 1) as f64 * p).round() as usize;
+        self.latencies[idx]
+    }
+}
+
+// The input file is removed when the bench is done, even on error.
+struct BenchInput(PathBuf);
+
+impl BenchInput {
+    fn new(content: &[u8]) -> Result<Self> {
+        let name = format!(
+            "rcli-bench-{}-{:x}",
+            std::process::id(),
+            rand::thread_rng().gen::<u64>()
+        );
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, content)?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> Result<&str> {
+        self.0
+            .to_str()
+            .ok_or_else(|| anyhow!("Invalid temp path {}", self.0.display()))
+    }
+}
+
+impl Drop for BenchInput {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Run an operation `iterations` times on `size` random bytes, timing each run.
+pub fn process_bench(op: &str, iterations: u32, size: usize) -> Result<BenchReport> {
+    if iterations == 0 {
+        return Err(anyhow!("The bench needs at least one iteration"));
+    }
+    let mut data = vec![0u8; size];
+    rand::thread_rng().fill_bytes(&mut data);
+    let input = match op {
+        "base64-decode" => BenchInput::new(STANDARD_ENGINE.encode(&data).as_bytes())?,
+        _ => BenchInput::new(&data)?,
+    };
+    let path = input.path()?;
+    let run = || -> Result<()> {
+        match op {
+            "base64-encode" => drop(process_encode(path, "standard")?),
+            "base64-decode" => drop(process_decode(path, "standard")?),
+            "hex-encode" => drop(process_hex_encode(path, false)?),
+            #[cfg(feature = "text")]
+            "encrypt" => drop(process_text_encrypt(&data, &[7u8; 32], "chacha20poly1305")?),
+            "sha256" | "blake3" => drop(process_digest(path, op)?),
+            _ => return Err(anyhow!("Unsupported bench operation: {}", op)),
+        }
+        Ok(())
+    };
+
+    let mut latencies = Vec::with_capacity(iterations as usize);
+    let start = Instant::now();
+    for _ in 0..iterations {
+        let begin = Instant::now();
+        run()?;
+        latencies.push(begin.elapsed());
+    }
+    let total = start.elapsed();
+    latencies.sort();
+    Ok(BenchReport {
+        iterations,
+        size,
+        total,
+        latencies,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_base64_encode() {
+        let report = process_bench("base64-encode", 20, 16).unwrap();
+        assert_eq!(report.iterations, 20);
+        assert!(report.ops_per_sec() > 0.0);
+        assert!(report.bytes_per_sec() > 0.0);
+        assert!(report.percentile(0.5) <= report.percentile(0.99));
+        assert!(process_bench("md5", 1, 16).is_err());
+        assert!(process_bench("sha256", 0, 16).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::{anyhow, Result};
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use std::{
+    io::{self, Read, Write},
+    ops::RangeInclusive,
+};
+use xz2::{read::XzDecoder, write::XzEncoder};
+
+// The levels each algorithm takes, and the one used when none is given.
+const LEVELS: &[(&str, RangeInclusive<u32>, u32)] =
+    &[("gzip", 0..=9, 6), ("zstd", 1..=22, 3), ("xz", 0..=9, 6)];
+
+const MAGIC: &[(&str, &[u8])] = &[
+    ("gzip", &[0x1f, 0x8b]),
+    ("zstd", &[0x28, 0xb5, 0x2f, 0xfd]),
+    ("xz", &[0xfd, b'7', b'z', b'X', b'Z', 0x00]),
+];
+
+/// The longest magic, xz's.
+pub const MAGIC_LEN: usize = 6;
+
+/// Compress the reader into the writer as a stream, returning the bytes read.
+pub fn process_compress(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    algo: &str,
+    level: Option<u32>,
+) -> Result<u64> {
+    let level = check_level(algo, level)?;
+    let read = match algo {
+        "gzip" => {
+            let mut encoder = GzEncoder::new(writer, Compression::new(level));
+            let read = io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+            read
+        }
+        "zstd" => {
+            let mut encoder = zstd::Encoder::new(writer, level as i32)?;
+            let read = io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+            read
+        }
+        "xz" => {
+            let mut encoder = XzEncoder::new(writer, level);
+            let read = io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+            read
+        }
+        _ => return Err(anyhow!("Unsupported compression algorithm: {}", algo)),
+    };
+    Ok(read)
+}
+
+/// Decompress the reader into the writer as a stream, returning the bytes written.
+pub fn process_decompress(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    algo: &str,
+) -> Result<u64> {
+    let written = match algo {
+        // Concatenated members, as `cat a.gz b.gz` makes, decode as one stream.
+        "gzip" => io::copy(&mut MultiGzDecoder::new(reader), writer)?,
+        "zstd" => io::copy(&mut zstd::Decoder::new(reader)?, writer)?,
+        "xz" => io::copy(&mut XzDecoder::new_multi_decoder(reader), writer)?,
+        _ => return Err(anyhow!("Unsupported compression algorithm: {}", algo)),
+    };
+    Ok(written)
+}
+
+/// The algorithm a stream starts with the magic bytes of, given at least its
+/// first `MAGIC_LEN` bytes.
+pub fn detect_compression(header: &[u8]) -> Option<&'static str> {
+    MAGIC
+        .iter()
+        .find(|(_, magic)| header.starts_with(magic))
+        .map(|(algo, _)| *algo)
+}
+
+/// The algorithm a file name's extension stands for, like `zstd` for `a.tar.zst`.
+pub fn compression_from_extension(name: &str) -> Option<&'static str> {
+    let (_, ext) = name.rsplit_once('.')?;
+    match ext {
+        "gz" | "tgz" => Some("gzip"),
+        "zst" | "zstd" => Some("zstd"),
+        "xz" | "txz" => Some("xz"),
+        _ => None,
+    }
+}
+
+fn check_level(algo: &str, level: Option<u32>) -> Result<u32> {
+    let (_, range, default) = LEVELS
+        .iter()
+        .find(|(name, _, _)| *name == algo)
+        .ok_or_else(|| anyhow!("Unsupported compression algorithm: {}", algo))?;
+    match level {
+        None => Ok(*default),
+        Some(level) if range.contains(&level) => Ok(level),
+        Some(level) => Err(anyhow!(
+            "{} levels are {} to {}, not {}",
+            algo,
+            range.start(),
+            range.end(),
+            level
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(algo: &str, level: Option<u32>) {
+        let data = "rcli compresses this line again and again\n".repeat(1000);
+        let mut compressed = Vec::new();
+        let read = process_compress(&mut data.as_bytes(), &mut compressed, algo, level).unwrap();
+        assert_eq!(read, data.len() as u64);
+        assert!(compressed.len() < data.len() / 10);
+
+        let mut decompressed = Vec::new();
+        process_decompress(&mut compressed.as_slice(), &mut decompressed, algo).unwrap();
+        assert_eq!(decompressed, data.as_bytes());
+    }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        for level in [None, Some(1), Some(9)] {
+            round_trip("gzip", level);
+        }
+    }
+
+    #[test]
+    fn test_zstd_round_trip() {
+        for level in [None, Some(1), Some(19)] {
+            round_trip("zstd", level);
+        }
+    }
+
+    #[test]
+    fn test_xz_round_trip() {
+        for level in [None, Some(0), Some(9)] {
+            round_trip("xz", level);
+        }
+    }
+
+    #[test]
+    fn test_compress_levels() {
+        let mut out = Vec::new();
+        assert!(process_compress(&mut &b"x"[..], &mut out, "gzip", Some(10)).is_err());
+        assert!(process_compress(&mut &b"x"[..], &mut out, "zstd", Some(0)).is_err());
+        assert!(process_compress(&mut &b"x"[..], &mut out, "lz4", None).is_err());
+    }
+
+    #[test]
+    fn test_detect_compression() {
+        for algo in ["gzip", "zstd", "xz"] {
+            let mut compressed = Vec::new();
+            process_compress(&mut &b"rcli"[..], &mut compressed, algo, None).unwrap();
+            assert_eq!(detect_compression(&compressed[..MAGIC_LEN]), Some(algo));
+        }
+        assert_eq!(detect_compression(b"plain text"), None);
+        assert_eq!(detect_compression(b""), None);
+    }
+
+    #[test]
+    fn test_compression_from_extension() {
+        assert_eq!(compression_from_extension("logs.tar.zst"), Some("zstd"));
+        assert_eq!(compression_from_extension("notes.txt.gz"), Some("gzip"));
+        assert_eq!(compression_from_extension("dump.xz"), Some("xz"));
+        assert_eq!(compression_from_extension("README"), None);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::read_contents;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+/// Convert the input between json, yaml and toml, through a json value.
+pub fn process_convert(input: &str, from: &str, to: &str) -> Result<String> {
+    let content = String::from_utf8(read_contents(input)?)?;
+    convert(&content, from, to)
+}
+
+/// The format a file name's extension stands for, like `yaml` for `a.yml`.
+pub fn data_format_from_extension(name: &str) -> Option<&'static str> {
+    let (_, ext) = name.rsplit_once('.')?;
+    match ext.to_ascii_lowercase().as_str() {
+        "json" => Some("json"),
+        "yaml" | "yml" => Some("yaml"),
+        "toml" => Some("toml"),
+        _ => None,
+    }
+}
+
+fn convert(content: &str, from: &str, to: &str) -> Result<String> {
+    let value: Value = match from {
+        "json" => serde_json::from_str(content)?,
+        "yaml" => serde_yaml::from_str(content)?,
+        "toml" => toml::from_str(content)?,
+        _ => return Err(anyhow!("Unsupported format: {}", from)),
+    };
+    let converted = match to {
+        "json" => serde_json::to_string_pretty(&value)?,
+        "yaml" => serde_yaml::to_string(&value)?,
+        // TOML has no null and its document must be a table.
+        "toml" => toml::to_string_pretty(&value)
+            .map_err(|e| anyhow!("Can't write this document as toml: {}", e))?,
+        _ => return Err(anyhow!("Unsupported format: {}", to)),
+    };
+    Ok(converted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMATS: [&str; 3] = ["json", "yaml", "toml"];
+
+    #[test]
+    fn test_convert_round_trip() {
+        let json = r#"{"name": "rcli", "version": 7, "tags": ["cli", "rust"], "http": {"port": 8080, "webdav": true}}"#;
+        let expected: Value = serde_json::from_str(json).unwrap();
+        for from in FORMATS {
+            let source = convert(json, "json", from).unwrap();
+            for to in FORMATS {
+                let converted = convert(&source, from, to).unwrap();
+                let back = convert(&converted, to, "json").unwrap();
+                let value: Value = serde_json::from_str(&back).unwrap();
+                assert_eq!(value, expected, "{} -> {}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn test_convert_errors() {
+        assert!(convert("[1, 2]", "json", "toml").is_err());
+        assert!(convert("{\"a\": null}", "json", "toml").is_err());
+        assert!(convert("not: [valid", "yaml", "json").is_err());
+        assert!(convert("{}", "json", "xml").is_err());
+    }
+
+    #[test]
+    fn test_data_format_from_extension() {
+        assert_eq!(data_format_from_extension("config.yml"), Some("yaml"));
+        assert_eq!(data_format_from_extension("Cargo.toml"), Some("toml"));
+        assert_eq!(data_format_from_extension("assets/jwks.json"), Some("json"));
+        assert_eq!(data_format_from_extension("README"), None);
+    }
+}
@@ -1,22 +1,28 @@
+use crate::get_reader;
 use csv::Reader;
-use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fs;
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct Player {
-    name: String,
-    position: String,
-    #[serde(rename = "DOB")]
-    dob: String,
-    nationality: String,
-    #[serde(rename = "Kit Number")]
-    kit: u8,
+pub fn process_csv(input: &str, output: &str, format: &str) -> anyhow::Result<()> {
+    let content = process_csv_convert(input, format)?;
+    fs::write(output, content)?;
+    Ok(())
 }
 
-pub fn process_csv(input: &str, output: &str, format: &str) -> anyhow::Result<()> {
-    let mut reader = Reader::from_path(input)?;
+// The converted content, for the caller to write.
+pub fn process_csv_convert(input: &str, format: &str) -> anyhow::Result<String> {
+    let ret = read_csv(input)?;
+    let content = match format {
+        "json" => serde_json::to_string_pretty(&ret)?,
+        "yaml" => serde_yaml::to_string(&ret)?,
+        _ => return Err(anyhow::anyhow!("Invalid format")),
+    };
+    Ok(content)
+}
+
+// Each record as an object keyed by the headers.
+pub(crate) fn read_csv(input: &str) -> anyhow::Result<Vec<Value>> {
+    let mut reader = Reader::from_reader(get_reader(input)?);
     let mut ret = Vec::new();
     let headers = reader.headers()?.clone();
     for result in reader.records() {
@@ -24,14 +30,7 @@ pub fn process_csv(input: &str, output: &str, format: &str) -> anyhow::Result<()
         let json_value = headers.iter().zip(record.iter()).collect::<Value>();
         ret.push(json_value);
     }
-
-    let content = match format {
-        "json" => serde_json::to_string_pretty(&ret)?,
-        "yaml" => serde_yaml::to_string(&ret)?,
-        _ => return Err(anyhow::anyhow!("Invalid format")),
-    };
-    fs::write(output, content)?;
-    Ok(())
+    Ok(ret)
 }
 
 #[cfg(test)]
@@ -0,0 +1,198 @@
+use crate::{get_reader, read_contents};
+use anyhow::{anyhow, Result};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256, Sha512};
+use std::io::{self, Write};
+
+const ALGORITHMS: &[&str] = &["sha256", "sha512", "blake3"];
+
+/// Hash the input as a stream and return the lowercase hex digest.
+pub fn process_digest(input: &str, algo: &str) -> Result<String> {
+    let mut reader = get_reader(input)?;
+    let digest = match algo {
+        "sha256" => format!("{:x}", hash_with(Sha256::new(), &mut reader)?.finalize()),
+        "sha512" => format!("{:x}", hash_with(Sha512::new(), &mut reader)?.finalize()),
+        "blake3" => hash_with(blake3::Hasher::new(), &mut reader)?
+            .finalize()
+            .to_hex()
+            .to_string(),
+        _ => return Err(anyhow!("Unsupported digest algorithm: {}", algo)),
+    };
+    Ok(digest)
+}
+
+/// Hash the inputs on `jobs` threads, default one per CPU, returning the
+/// digests in the order of the inputs.
+pub fn process_digest_files(
+    inputs: &[String],
+    algo: &str,
+    jobs: Option<usize>,
+) -> Result<Vec<String>> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.unwrap_or(0))
+        .build()?;
+    pool.install(|| {
+        inputs
+            .par_iter()
+            .map(|input| process_digest(input, algo))
+            .collect()
+    })
+}
+
+/// Check every `<hex>  <filename>` line of a manifest, returning each file
+/// with whether it matched. A file that can't be read counts as a mismatch.
+pub fn process_digest_check(manifest: &str, algo: &str) -> Result<Vec<(String, bool)>> {
+    let manifest = String::from_utf8(read_contents(manifest)?)?;
+    let mut results = Vec::new();
+    for line in manifest.lines().filter(|line| !line.trim().is_empty()) {
+        let (expected, name) = parse_manifest_line(line)
+            .ok_or_else(|| anyhow!("Malformed checksum line: {}", line))?;
+        results.push((name.to_string(), file_matches(name, algo, expected)?));
+    }
+    Ok(results)
+}
+
+/// Like `process_digest_check`, but each line names its own algorithm as in
+/// `<algo>:<hex>  <filename>`, so one manifest can mix them.
+pub fn process_verify_manifest(manifest: &str) -> Result<Vec<(String, bool)>> {
+    let manifest = String::from_utf8(read_contents(manifest)?)?;
+    let mut results = Vec::new();
+    for line in manifest.lines().filter(|line| !line.trim().is_empty()) {
+        let (algo, (expected, name)) = line
+            .split_once(':')
+            .and_then(|(algo, rest)| Some((algo, parse_manifest_line(rest)?)))
+            .ok_or_else(|| anyhow!("Malformed manifest line: {}", line))?;
+        results.push((name.to_string(), file_matches(name, algo, expected)?));
+    }
+    Ok(results)
+}
+
+// An unknown algorithm is an error, a file that can't be read just doesn't match.
+fn file_matches(name: &str, algo: &str, expected: &str) -> Result<bool> {
+    if !ALGORITHMS.contains(&algo) {
+        return Err(anyhow!("Unsupported digest algorithm: {}", algo));
+    }
+    Ok(process_digest(name, algo).is_ok_and(|digest| digest.eq_ignore_ascii_case(expected)))
+}
+
+fn hash_with<W: Write>(mut hasher: W, reader: &mut impl io::Read) -> Result<W> {
+    io::copy(reader, &mut hasher)?;
+    Ok(hasher)
+}
+
+// The coreutils format, where a `*` before the name marks binary mode.
+fn parse_manifest_line(line: &str) -> Option<(&str, &str)> {
+    let (digest, name) = line.split_once(' ')?;
+    let name = name.strip_prefix([' ', '*'])?;
+    if digest.is_empty() || name.is_empty() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((digest, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_algorithms() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "abc").unwrap();
+        let path = file.path().to_str().unwrap();
+        assert_eq!(
+            process_digest(path, "sha256").unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(process_digest(path, "sha512").unwrap().len(), 128);
+        assert_eq!(
+            process_digest(path, "blake3").unwrap(),
+            blake3::hash(b"abc").to_hex().as_str()
+        );
+        assert!(process_digest(path, "md5").is_err());
+    }
+
+    #[test]
+    fn test_digest_files_parallel() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = (0..64)
+            .map(|i| {
+                let path = dir.path().join(format!("{}.txt", i));
+                std::fs::write(&path, "x".repeat(i * 1000)).unwrap();
+                path.to_str().unwrap().to_string()
+            })
+            .collect::<Vec<_>>();
+        let serial = files
+            .iter()
+            .map(|file| process_digest(file, "blake3").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            process_digest_files(&files, "blake3", Some(4)).unwrap(),
+            serial
+        );
+        assert_eq!(
+            process_digest_files(&files, "blake3", None).unwrap(),
+            serial
+        );
+        assert!(process_digest_files(&files, "md5", Some(4)).is_err());
+    }
+
+    #[test]
+    fn test_digest_check_manifest() {
+        let fixtures = ["assets/juventus.csv", "assets/encode.b64"];
+        let manifest = fixtures
+            .iter()
+            .map(|name| format!("{}  {}\n", process_digest(name, "sha256").unwrap(), name))
+            .collect::<String>();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_str().unwrap();
+
+        std::fs::write(path, &manifest).unwrap();
+        let results = process_digest_check(path, "sha256").unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(_, ok)| *ok));
+
+        let flipped = if manifest.starts_with('0') { "1" } else { "0" };
+        let corrupted = format!("{}{}", flipped, &manifest[1..]);
+        std::fs::write(path, corrupted).unwrap();
+        let results = process_digest_check(path, "sha256").unwrap();
+        assert_eq!(
+            results,
+            vec![
+                ("assets/juventus.csv".to_string(), false),
+                ("assets/encode.b64".to_string(), true)
+            ]
+        );
+
+        std::fs::write(path, "not a checksum line\n").unwrap();
+        assert!(process_digest_check(path, "sha256").is_err());
+    }
+
+    #[test]
+    fn test_verify_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good.txt");
+        let tampered = dir.path().join("tampered.txt");
+        std::fs::write(&good, "release notes").unwrap();
+        std::fs::write(&tampered, "release bundle").unwrap();
+        let (good, tampered) = (good.to_str().unwrap(), tampered.to_str().unwrap());
+        let manifest = format!(
+            "sha256:{}  {}\nblake3:{}  {}\n",
+            process_digest(good, "sha256").unwrap(),
+            good,
+            process_digest(tampered, "blake3").unwrap(),
+            tampered
+        );
+        std::fs::write(tampered, "release bundle, tampered").unwrap();
+        let path = dir.path().join("sums.txt");
+        std::fs::write(&path, manifest).unwrap();
+
+        let results = process_verify_manifest(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            results,
+            vec![(good.to_string(), true), (tampered.to_string(), false)]
+        );
+
+        std::fs::write(&path, format!("md5:abcd  {}\n", good)).unwrap();
+        assert!(process_verify_manifest(path.to_str().unwrap()).is_err());
+    }
+}
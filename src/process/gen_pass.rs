@@ -52,4 +52,15 @@ mod tests {
         process_genpass(16, true, true, true, true).unwrap();
         process_genpass(16, true, true, true, false).unwrap();
     }
+
+    #[test]
+    fn test_process_genpass_returns_bytes() {
+        // The password is only returned, printing it is up to the caller.
+        let password = process_genpass(24, true, false, true, false).unwrap();
+        assert_eq!(password.len(), 24);
+        assert!(password
+            .iter()
+            .all(|b| LOWER.contains(b) || DIGITS.contains(b)));
+        assert!(password.iter().any(|b| DIGITS.contains(b)));
+    }
 }
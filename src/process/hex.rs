@@ -0,0 +1,62 @@
+use crate::read_contents;
+use anyhow::{anyhow, Result};
+
+pub fn process_hex_encode(input: &str, uppercase: bool) -> Result<String> {
+    let buf = read_contents(input)?;
+    let encoded = if uppercase {
+        hex::encode_upper(buf)
+    } else {
+        hex::encode(buf)
+    };
+    Ok(encoded)
+}
+
+// Whitespace is ignored and every group may carry a `0x` prefix,
+// so dumps like `0xde 0xad` decode as well as `dead`.
+pub fn process_hex_decode(input: &str) -> Result<Vec<u8>> {
+    let buf = String::from_utf8(read_contents(input)?)?;
+    let digits = buf
+        .split_whitespace()
+        .map(|group| {
+            group
+                .strip_prefix("0x")
+                .or_else(|| group.strip_prefix("0X"))
+                .unwrap_or(group)
+        })
+        .collect::<String>();
+    hex::decode(&digits).map_err(|e| anyhow!("Invalid hex input: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(content: &[u8]) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), content).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let input = "assets/decode.b64";
+        let original = std::fs::read(input).unwrap();
+        for uppercase in [false, true] {
+            let encoded = process_hex_encode(input, uppercase).unwrap();
+            assert_eq!(encoded.len(), original.len() * 2);
+            let file = write_temp(encoded.as_bytes());
+            let decoded = process_hex_decode(file.path().to_str().unwrap()).unwrap();
+            assert_eq!(decoded, original);
+        }
+    }
+
+    #[test]
+    fn test_hex_decode_tolerant() {
+        let file = write_temp(b"0xDE 0xad\n\tbe 0XeF\n");
+        let decoded = process_hex_decode(file.path().to_str().unwrap()).unwrap();
+        assert_eq!(decoded, [0xde, 0xad, 0xbe, 0xef]);
+
+        let file = write_temp(b"0xd 0xz1");
+        assert!(process_hex_decode(file.path().to_str().unwrap()).is_err());
+    }
+}
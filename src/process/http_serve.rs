@@ -1,26 +1,132 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use axum::{
-    extract::{OriginalUri, Request, State},
-    http::StatusCode,
-    response::{Html, IntoResponse, Redirect, Response},
-    routing::get,
+    extract::{OriginalUri, Query, Request, State},
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Json, Redirect, Response},
+    routing::{get, MethodRouter},
     Router,
 };
 use chrono::{DateTime, Utc};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ipnet::IpNet;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use minijinja::Environment;
-use percent_encoding::percent_decode;
-use serde::Serialize;
+use percent_encoding::{percent_decode, utf8_percent_encode, NON_ALPHANUMERIC};
+use qrcode::{render::unicode::Dense1x2, QrCode};
+use serde::{Deserialize, Serialize};
 use std::{
-    net::{IpAddr, SocketAddr},
+    collections::HashMap,
+    io::Write,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     path::{Component, Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
 };
-use tokio::fs;
-use tower_http::{services::ServeDir, trace::TraceLayer};
-use tracing::{debug, error, info};
+use tokio::{fs, net::TcpListener};
+use tower_http::{
+    limit::RequestBodyLimitLayer,
+    services::{ServeDir, ServeFile},
+    set_header::SetResponseHeaderLayer,
+    trace::TraceLayer,
+};
+use tracing::{debug, error, info, warn};
+
+mod auth;
+mod conn;
+mod ip_filter;
+mod preview;
+mod upload;
+mod webdav;
+
+pub struct HttpServeConfig {
+    pub path: PathBuf,
+    pub addr: IpAddr,
+    pub port: u16,
+    pub daemon: bool,
+    pub follow_symlinks: bool,
+    pub webdav: bool,
+    pub index: String,
+    pub spa: bool,
+    pub exclude: Vec<String>,
+    pub theme: String,
+    pub template: Option<PathBuf>,
+    pub qr: bool,
+    pub metrics: bool,
+    pub max_body: Option<usize>,
+    pub headers: Vec<String>,
+    pub force_download: bool,
+    pub mime: Vec<String>,
+    pub protect: Vec<String>,
+    pub open: bool,
+    pub force_open: bool,
+    pub http2: bool,
+    pub keep_alive: u64,
+    pub request_timeout: u64,
+    pub mode: String,
+    pub precompressed: bool,
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+impl Default for HttpServeConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("."),
+            addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 8080,
+            daemon: false,
+            follow_symlinks: false,
+            webdav: false,
+            index: "index.html".to_string(),
+            spa: false,
+            exclude: Vec::new(),
+            theme: "auto".to_string(),
+            template: None,
+            qr: false,
+            metrics: false,
+            max_body: None,
+            headers: Vec::new(),
+            force_download: false,
+            mime: Vec::new(),
+            protect: Vec::new(),
+            open: false,
+            force_open: false,
+            http2: false,
+            keep_alive: 75,
+            request_timeout: 30,
+            mode: "ro".to_string(),
+            precompressed: false,
+            allow: Vec::new(),
+            deny: Vec::new(),
+        }
+    }
+}
 
+#[derive(Default)]
 struct HttpServeState {
     path: PathBuf,
+    follow_symlinks: bool,
+    index: String,
+    spa: bool,
+    exclude: GlobSet,
+    theme: String,
+    template: Option<String>,
+    started: DateTime<Utc>,
+    metrics: Option<PrometheusHandle>,
+    force_download: bool,
+    mime: HashMap<String, HeaderValue>,
+    protect: Vec<auth::ProtectRule>,
+    writable: bool,
+    precompressed: bool,
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HealthStatus {
+    status: String,
+    uptime_secs: i64,
 }
 
 #[derive(Serialize)]
@@ -28,6 +134,68 @@ struct DirList {
     entries: Vec<DirEntry>,
 }
 
+#[derive(Serialize)]
+struct IndexPage {
+    breadcrumbs: Vec<Breadcrumb>,
+    entries: Vec<DirEntry>,
+    pagination: Pagination,
+    columns: Vec<SortLink>,
+    query: String,
+    theme: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ServeQuery {
+    q: Option<String>,
+    dl: Option<String>,
+    page: Option<usize>,
+    per_page: Option<usize>,
+    preview: Option<String>,
+    sort: Option<SortKey>,
+    order: Option<SortOrder>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortKey {
+    #[default]
+    Name,
+    Date,
+    Size,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+// A clickable column header of the listing.
+#[derive(Debug, Serialize)]
+struct SortLink {
+    name: String,
+    href: String,
+    indicator: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Pagination {
+    page: usize,
+    pages: usize,
+    prev: Option<String>,
+    next: Option<String>,
+}
+
+const DEFAULT_PER_PAGE: usize = 1000;
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Breadcrumb {
+    name: String,
+    path: String,
+}
+
 #[derive(Serialize)]
 struct DirEntry {
     path: String,
@@ -36,35 +204,313 @@ struct DirEntry {
     icon: String,
     update: String,
     size: String,
+    #[serde(skip)]
+    len: u64,
+    #[serde(skip)]
+    modified: DateTime<Utc>,
 }
 
-pub async fn process_http_serve(
-    path: PathBuf,
-    addr: &IpAddr,
-    port: u16,
-    _daemon: bool,
-) -> Result<()> {
-    tracing_subscriber::fmt::init();
+/// Serve until stopped. The only output, the QR code with --qr, goes to `out`,
+/// everything else is logged.
+pub async fn process_http_serve(config: HttpServeConfig, out: &mut dyn Write) -> Result<()> {
     info!("Starting http server...");
-    let shared_state = Arc::new(HttpServeState { path: path.clone() });
+    let addr = SocketAddr::new(config.addr, config.port);
+    let qr = config.qr;
+    let (open, force_open) = (config.open, config.force_open);
+    let opts = conn::ConnOptions {
+        http2: config.http2,
+        keep_alive: config.keep_alive,
+        request_timeout: config.request_timeout,
+    };
+    let app = build_app(config)?;
+    let listener = TcpListener::bind(addr).await?;
+    info!("Server listening on: {}", addr);
+    if qr {
+        writeln!(out, "{}", render_qr(&format!("http://{}/", addr))?)?;
+        out.flush()?;
+    }
+    if open || force_open {
+        open_browser(addr, force_open);
+    }
+    conn::serve(listener, app, opts).await
+}
+
+fn build_app(config: HttpServeConfig) -> Result<Router> {
+    let mut exclude = GlobSetBuilder::new();
+    for pattern in &config.exclude {
+        exclude.add(Glob::new(pattern)?);
+    }
+
+    let shared_state = Arc::new(HttpServeState {
+        path: config.path.clone(),
+        follow_symlinks: config.follow_symlinks,
+        index: config.index.clone(),
+        spa: config.spa,
+        exclude: exclude.build()?,
+        theme: config.theme.clone(),
+        template: config.template.as_deref().map(load_template).transpose()?,
+        started: Utc::now(),
+        force_download: config.force_download,
+        mime: config
+            .mime
+            .iter()
+            .map(|m| parse_mime(m))
+            .collect::<Result<_>>()?,
+        protect: config
+            .protect
+            .iter()
+            .map(|rule| auth::ProtectRule::parse(rule))
+            .collect::<Result<_>>()?,
+        writable: config.mode == "rw",
+        precompressed: config.precompressed,
+        allow: parse_nets(&config.allow)?,
+        deny: parse_nets(&config.deny)?,
+        metrics: if config.metrics {
+            Some(metrics_handle())
+        } else {
+            None
+        },
+    });
+
+    // A single file is served at the root, without any directory listing.
+    if shared_state.path.is_file() {
+        info!("Serving single file: {}", shared_state.path.display());
+        let app = Router::new()
+            .route("/healthz", get(health_check))
+            .route("/", get(single_file_service));
+        return with_layers(app, &config, shared_state);
+    }
 
     // Create a router for file service handler.
     // Note that the path must include a '/' and also follow the '/*key' pattern.
+    // WebDAV methods are not standard, so they are handled by the method fallback.
+    // Write methods are only registered in read-write mode.
+    let mut file_handler: MethodRouter<Arc<HttpServeState>> = get(file_service);
+    if shared_state.writable {
+        info!("Serving in read-write mode");
+        file_handler = file_handler
+            .put(upload::put_file)
+            .delete(upload::delete_path);
+    } else {
+        info!("Serving in read-only mode");
+    }
+    if config.webdav {
+        info!("WebDAV is enabled");
+        file_handler = file_handler.fallback(webdav::webdav_service);
+    }
     let file_app = Router::new()
-        .route("/", get(file_service))
-        .route("/*key", get(file_service));
+        .route("/", file_handler.clone())
+        .route("/*key", file_handler);
 
     // Customize the path here and integrate it with file_app.
     // Note that it needs to end with a slash.
     let app = Router::new()
-        .nest("/", file_app)
+        .route("/healthz", get(health_check))
+        .nest("/", file_app);
+    with_layers(app, &config, shared_state)
+}
+
+// The middleware every server gets, whether it serves a directory or a single file.
+fn with_layers(
+    mut app: Router<Arc<HttpServeState>>,
+    config: &HttpServeConfig,
+    shared_state: Arc<HttpServeState>,
+) -> Result<Router> {
+    if shared_state.metrics.is_some() {
+        info!("Metrics are exported on /metrics");
+        app = app
+            .route("/metrics", get(metrics_service))
+            .layer(middleware::from_fn(track_metrics));
+    }
+    if !shared_state.protect.is_empty() {
+        info!(
+            "Password protection enabled for {} path(s)",
+            shared_state.protect.len()
+        );
+        app = app.layer(middleware::from_fn_with_state(
+            shared_state.clone(),
+            auth::protect_paths,
+        ));
+    }
+    if let Some(limit) = config.max_body {
+        info!("Request body limit: {} bytes", limit);
+        app = app.layer(RequestBodyLimitLayer::new(limit));
+    }
+    for header in &config.headers {
+        let (name, value) = parse_header(header)?;
+        app = app.layer(SetResponseHeaderLayer::overriding(name, value));
+    }
+    // Checked before anything else so refused clients learn nothing about the files.
+    if has_ip_rules(&shared_state) {
+        info!(
+            "IP filtering enabled: {} allowed, {} denied network(s)",
+            shared_state.allow.len(),
+            shared_state.deny.len()
+        );
+        app = app.layer(middleware::from_fn_with_state(
+            shared_state.clone(),
+            ip_filter::filter_ips,
+        ));
+    }
+    if config.request_timeout > 0 {
+        app = with_request_timeout(app, Duration::from_secs(config.request_timeout));
+    }
+    Ok(app
         .layer(TraceLayer::new_for_http())
-        .with_state(shared_state);
-    let addr = SocketAddr::new(*addr, port);
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    info!("Server listening on: {}", addr);
-    axum::serve(listener, app).await?;
-    Ok(())
+        .with_state(shared_state))
+}
+
+// Parse a `.ext=type/subtype` pair into a lowercase extension and its content type.
+fn parse_mime(mime: &str) -> Result<(String, HeaderValue)> {
+    let (ext, content_type) = mime
+        .split_once('=')
+        .ok_or_else(|| anyhow!("invalid mime `{}`, expected `.ext=type/subtype`", mime))?;
+    let ext = ext.trim().trim_start_matches('.').to_lowercase();
+    let content_type = content_type.trim();
+    if ext.is_empty() || !content_type.contains('/') {
+        return Err(anyhow!(
+            "invalid mime `{}`, expected `.ext=type/subtype`",
+            mime
+        ));
+    }
+    Ok((ext, HeaderValue::try_from(content_type)?))
+}
+
+// Parse a `Name: Value` pair into a response header.
+fn parse_header(header: &str) -> Result<(HeaderName, HeaderValue)> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| anyhow!("invalid header `{}`, expected `Name: Value`", header))?;
+    let name = HeaderName::try_from(name.trim())
+        .map_err(|e| anyhow!("invalid header name in `{}`: {}", header, e))?;
+    let value = HeaderValue::try_from(value.trim())
+        .map_err(|e| anyhow!("invalid header value in `{}`: {}", header, e))?;
+    Ok((name, value))
+}
+
+fn parse_nets(cidrs: &[String]) -> Result<Vec<IpNet>> {
+    cidrs
+        .iter()
+        .map(|cidr| {
+            cidr.parse::<IpNet>()
+                // A bare address is a network of one.
+                .or_else(|_| cidr.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| anyhow!("invalid network `{}`, expected CIDR like 10.0.0.0/8", cidr))
+        })
+        .collect()
+}
+
+fn has_ip_rules(state: &HttpServeState) -> bool {
+    !state.allow.is_empty() || !state.deny.is_empty()
+}
+
+// Give up on reads that take too long to answer, the client gets a 408.
+// Uploads, moves and deletes can rightly take longer and aren't limited, a
+// stalled one is still dropped once the connection is idle past --keep-alive.
+fn with_request_timeout<S>(app: Router<S>, timeout: Duration) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    app.layer(middleware::from_fn(
+        move |req: Request, next: Next| async move {
+            if !req.method().is_safe() {
+                return next.run(req).await;
+            }
+            match tokio::time::timeout(timeout, next.run(req)).await {
+                Ok(res) => res,
+                Err(_) => StatusCode::REQUEST_TIMEOUT.into_response(),
+            }
+        },
+    ))
+}
+
+// The recorder is global, so it is installed once and shared by every server.
+fn metrics_handle() -> PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+    HANDLE
+        .get_or_init(|| {
+            let recorder = PrometheusBuilder::new()
+                .set_buckets(&[
+                    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+                ])
+                .expect("buckets won't be empty")
+                .build_recorder();
+            let handle = recorder.handle();
+            if metrics::set_global_recorder(recorder).is_err() {
+                error!("A global metrics recorder is already installed");
+            }
+            handle
+        })
+        .clone()
+}
+
+async fn track_metrics(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    let res = next.run(req).await;
+    let status = res.status().as_u16().to_string();
+    let labels = [("method", method), ("status", status)];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels)
+        .record(start.elapsed().as_secs_f64());
+    let bytes = res
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if let Some(bytes) = bytes {
+        metrics::counter!("http_response_bytes_total").increment(bytes);
+    }
+    res
+}
+
+async fn metrics_service(State(state): State<Arc<HttpServeState>>) -> Response {
+    match &state.metrics {
+        Some(handle) => handle.render().into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn health_check(State(state): State<Arc<HttpServeState>>) -> Json<HealthStatus> {
+    let uptime = Utc::now() - state.started;
+    Json(HealthStatus {
+        status: "ok".to_string(),
+        uptime_secs: uptime.num_seconds(),
+    })
+}
+
+// The url a local browser can reach, an unspecified address is reached via loopback.
+fn browser_url(addr: SocketAddr) -> String {
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    format!("http://{}/", SocketAddr::new(ip, addr.port()))
+}
+
+fn open_browser(addr: SocketAddr, force: bool) {
+    let ip = addr.ip();
+    if !force && !ip.is_loopback() && !ip.is_unspecified() {
+        warn!("Not opening the browser for a public address, use --force-open to do so");
+        return;
+    }
+    let url = browser_url(addr);
+    info!("Opening {} in the browser", url);
+    if let Err(e) = open::that_detached(&url) {
+        warn!("Failed to open the browser: {:?}", e);
+    }
+}
+
+// Render the url as a QR code made of unicode half blocks for the terminal.
+fn render_qr(url: &str) -> Result<String> {
+    let code = QrCode::new(url)?;
+    let image = code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build();
+    Ok(image)
 }
 
 async fn file_service(State(state): State<Arc<HttpServeState>>, req: Request) -> Response {
@@ -72,13 +518,17 @@ async fn file_service(State(state): State<Arc<HttpServeState>>, req: Request) ->
 
     // Concatenate local file path
     let req_path = req.uri().path();
-    let file_path = match build_and_validate_path(state.path.clone(), req_path) {
+    let file_path = match build_and_validate_path(&state.path, req_path, state.follow_symlinks) {
         Some(path) => path,
         None => {
             error!("Invalid path: {:?}", req_path);
             return StatusCode::BAD_REQUEST.into_response();
         }
     };
+    if is_excluded(&state, &file_path) {
+        debug!("Excluded path: {:?}", req_path);
+        return StatusCode::NOT_FOUND.into_response();
+    }
 
     // check the path, if it is a directory, Generate a directory file list.
     // If it is a file, serve the file.
@@ -87,29 +537,49 @@ async fn file_service(State(state): State<Arc<HttpServeState>>, req: Request) ->
         match check_path_suffix(&req) {
             Some(res) => res,
             None => {
-                match get_dir_list(file_path).await {
-                    Ok(metadata) => {
-                        // Render an HTML page.
-                        match render_template(metadata) {
-                            Ok(rendered) => Html(rendered).into_response(),
-                            Err(e) => {
-                                error!("Error rendering template: {:?}", e);
-                                StatusCode::INTERNAL_SERVER_ERROR.into_response()
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Error reading directory: {:?}", e);
-                        StatusCode::INTERNAL_SERVER_ERROR.into_response()
-                    }
+                // Serve the index file instead of the listing if the directory has one.
+                if let Some(index) = find_index(&file_path, &state) {
+                    return serve_file(index, req).await;
                 }
+                let query = Query::<ServeQuery>::try_from_uri(req.uri())
+                    .map(|q| q.0)
+                    .unwrap_or_default();
+                dir_listing(&state, &file_path, original_path(&req), query).await
             }
         }
+    } else if state.spa && !file_path.exists() {
+        // Let the client side router handle unknown paths of a single-page app.
+        match find_index(&state.path, &state) {
+            Some(index) => serve_file(index, req).await,
+            None => StatusCode::NOT_FOUND.into_response(),
+        }
     } else {
-        // use tower-http ServeDir to serve files
+        let query = Query::<ServeQuery>::try_from_uri(req.uri())
+            .map(|q| q.0)
+            .unwrap_or_default();
+        // Render known text files as highlighted HTML if requested with `?preview`.
+        if query.preview.is_some() && file_path.is_file() {
+            let theme = state.theme.clone();
+            let path = file_path.clone();
+            match tokio::task::spawn_blocking(move || preview::render_preview(&path, &theme)).await
+            {
+                Ok(Ok(Some(rendered))) => return Html(rendered).into_response(),
+                Ok(Ok(None)) => debug!("No preview for {:?}", file_path),
+                Ok(Err(e)) => error!("Error rendering preview: {:?}", e),
+                Err(e) => error!("Error rendering preview: {:?}", e),
+            }
+        }
+        // Download instead of rendering inline if forced or requested with `?dl`.
+        let download = state.force_download || query.dl.is_some();
+        // use tower-http ServeDir to serve files, it sets Last-Modified and answers
+        // Range requests with 206 and If-Modified-Since requests with 304
         let mut dir_service = ServeDir::new(state.path.clone());
+        // Prefer a `.gz` sibling if the client accepts gzip.
+        if state.precompressed {
+            dir_service = dir_service.precompressed_gzip();
+        }
         match dir_service.try_call(req).await {
-            Ok(res) => res.into_response(),
+            Ok(res) => with_file_headers(&state, &file_path, download, res.into_response()),
             Err(e) => {
                 error!("Error serving file: {:?}", e);
                 StatusCode::INTERNAL_SERVER_ERROR.into_response()
@@ -118,7 +588,254 @@ async fn file_service(State(state): State<Arc<HttpServeState>>, req: Request) ->
     }
 }
 
-fn build_and_validate_path(base_path: impl AsRef<Path>, req_path: &str) -> Option<PathBuf> {
+// Serve the file rcli was started with, at the root.
+async fn single_file_service(State(state): State<Arc<HttpServeState>>, req: Request) -> Response {
+    let download = state.force_download
+        || Query::<ServeQuery>::try_from_uri(req.uri()).is_ok_and(|q| q.dl.is_some());
+    let res = serve_file(state.path.clone(), req).await;
+    with_file_headers(&state, &state.path, download, res)
+}
+
+// Apply the --mime overrides, and ask to download with --force-download or `?dl`.
+fn with_file_headers(
+    state: &HttpServeState,
+    file_path: &Path,
+    download: bool,
+    mut res: Response,
+) -> Response {
+    if !res.status().is_success() {
+        return res;
+    }
+    let mime = file_path
+        .extension()
+        .and_then(|ext| state.mime.get(&ext.to_string_lossy().to_lowercase()));
+    if let Some(mime) = mime {
+        res.headers_mut().insert(header::CONTENT_TYPE, mime.clone());
+    }
+    if download {
+        let value = file_path
+            .file_name()
+            .and_then(|name| content_disposition(&name.to_string_lossy()).ok());
+        if let Some(value) = value {
+            res.headers_mut().insert(header::CONTENT_DISPOSITION, value);
+        }
+    }
+    res
+}
+
+// Quote the filename for ASCII clients and add an encoded one for non-ASCII names.
+fn content_disposition(name: &str) -> Result<HeaderValue> {
+    let fallback: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() && c != '"' && c != '\\' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let mut value = format!("attachment; filename=\"{}\"", fallback);
+    if !name.is_ascii() {
+        value.push_str(&format!(
+            "; filename*=UTF-8''{}",
+            utf8_percent_encode(name, NON_ALPHANUMERIC)
+        ));
+    }
+    Ok(HeaderValue::try_from(value)?)
+}
+
+// A path is excluded if its path relative to the root, or any of its components, matches.
+fn is_excluded(state: &HttpServeState, path: &Path) -> bool {
+    if state.exclude.is_empty() {
+        return false;
+    }
+    let relative = path.strip_prefix(&state.path).unwrap_or(path);
+    state.exclude.is_match(relative)
+        || relative
+            .components()
+            .any(|c| state.exclude.is_match(c.as_os_str()))
+}
+
+async fn dir_listing(
+    state: &HttpServeState,
+    dir: &Path,
+    req_path: &str,
+    query: ServeQuery,
+) -> Response {
+    match get_dir_list(dir, state, query.q.as_deref()).await {
+        Ok(metadata) => {
+            // Render an HTML page.
+            let (sort, order) = (
+                query.sort.unwrap_or_default(),
+                query.order.unwrap_or_default(),
+            );
+            let mut entries = metadata.entries;
+            sort_entries(&mut entries, sort, order);
+            let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE);
+            let (entries, pagination) = paginate(entries, query.page.unwrap_or(1), per_page);
+            let params = list_params(query.q.as_deref(), sort, order);
+            let page = IndexPage {
+                breadcrumbs: build_breadcrumbs(req_path),
+                entries,
+                pagination: pagination.with_links(per_page, &params),
+                columns: sort_links(query.q.as_deref(), sort, order),
+                query: query.q.unwrap_or_default(),
+                theme: state.theme.clone(),
+            };
+            match render_template(page, state.template.as_deref()) {
+                Ok(rendered) => Html(rendered).into_response(),
+                Err(e) => {
+                    error!("Error rendering template: {:?}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                }
+            }
+        }
+        Err(e) => {
+            error!("Error reading directory: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+// Return the entries of the 1-based page, the page is clamped to the existing pages.
+fn paginate(entries: Vec<DirEntry>, page: usize, per_page: usize) -> (Vec<DirEntry>, Pagination) {
+    let per_page = per_page.max(1);
+    let pages = entries.len().div_ceil(per_page).max(1);
+    let page = page.clamp(1, pages);
+    let entries = entries
+        .into_iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .collect();
+    let pagination = Pagination {
+        page,
+        pages,
+        prev: None,
+        next: None,
+    };
+    (entries, pagination)
+}
+
+impl SortKey {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Date => "date",
+            SortKey::Size => "size",
+        }
+    }
+}
+
+impl SortOrder {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+
+    fn reverse(self) -> Self {
+        match self {
+            SortOrder::Asc => SortOrder::Desc,
+            SortOrder::Desc => SortOrder::Asc,
+        }
+    }
+}
+
+fn sort_entries(entries: &mut [DirEntry], sort: SortKey, order: SortOrder) {
+    entries.sort_by(|a, b| {
+        let ordering = match sort {
+            SortKey::Name => a.name.cmp(&b.name),
+            SortKey::Date => a.modified.cmp(&b.modified),
+            SortKey::Size => a.len.cmp(&b.len),
+        };
+        match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    });
+}
+
+// The filter and sort parameters carried over by pagination links, like: &q=a&sort=size
+fn list_params(q: Option<&str>, sort: SortKey, order: SortOrder) -> String {
+    let mut params = String::new();
+    if let Some(q) = q.filter(|q| !q.is_empty()) {
+        params.push_str(&format!("&q={}", utf8_percent_encode(q, NON_ALPHANUMERIC)));
+    }
+    if sort != SortKey::default() || order != SortOrder::default() {
+        params.push_str(&format!("&sort={}&order={}", sort.as_str(), order.as_str()));
+    }
+    params
+}
+
+// Clicking the active column flips the order, other columns start ascending.
+fn sort_links(q: Option<&str>, sort: SortKey, order: SortOrder) -> Vec<SortLink> {
+    [
+        ("Name", SortKey::Name),
+        ("Last Modified", SortKey::Date),
+        ("Size", SortKey::Size),
+    ]
+    .into_iter()
+    .map(|(name, key)| {
+        let (next, indicator) = match (key == sort, order) {
+            (true, SortOrder::Asc) => (order.reverse(), "▲"),
+            (true, SortOrder::Desc) => (order.reverse(), "▼"),
+            (false, _) => (SortOrder::Asc, ""),
+        };
+        let mut href = format!("?sort={}&order={}", key.as_str(), next.as_str());
+        if let Some(q) = q.filter(|q| !q.is_empty()) {
+            href.push_str(&format!("&q={}", utf8_percent_encode(q, NON_ALPHANUMERIC)));
+        }
+        SortLink {
+            name: name.to_string(),
+            href,
+            indicator: indicator.to_string(),
+        }
+    })
+    .collect()
+}
+
+impl Pagination {
+    fn with_links(mut self, per_page: usize, params: &str) -> Self {
+        let link = |page: usize| format!("?page={}&per_page={}{}", page, per_page, params);
+        if self.page > 1 {
+            self.prev = Some(link(self.page - 1));
+        }
+        if self.page < self.pages {
+            self.next = Some(link(self.page + 1));
+        }
+        self
+    }
+}
+
+fn find_index(dir: &Path, state: &HttpServeState) -> Option<PathBuf> {
+    if state.index.is_empty() {
+        return None;
+    }
+    let index = dir.join(&state.index);
+    if index.is_file() && (state.follow_symlinks || is_within_root(&state.path, &index)) {
+        Some(index)
+    } else {
+        None
+    }
+}
+
+async fn serve_file(path: PathBuf, req: Request) -> Response {
+    match ServeFile::new(path).try_call(req).await {
+        Ok(res) => res.into_response(),
+        Err(e) => {
+            error!("Error serving file: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn build_and_validate_path(
+    base_path: impl AsRef<Path>,
+    req_path: &str,
+    follow_symlinks: bool,
+) -> Option<PathBuf> {
     let path = req_path.trim_start_matches('/');
     let path_decoded = percent_decode(path.as_bytes()).decode_utf8().ok()?;
     let path_decoded = Path::new(&*path_decoded);
@@ -139,15 +856,33 @@ fn build_and_validate_path(base_path: impl AsRef<Path>, req_path: &str) -> Optio
             Component::Prefix(_) | Component::ParentDir | Component::RootDir => return None,
         }
     }
+    // A symlink may still point outside the root, so check the resolved target.
+    if !follow_symlinks && !is_within_root(base_path, &path_to_file) {
+        return None;
+    }
     Some(path_to_file)
 }
 
-fn check_path_suffix(req: &Request) -> Option<Response> {
-    let original_uri = if let Some(path) = req.extensions().get::<OriginalUri>() {
+// Check that the resolved path stays inside the resolved root.
+// Paths that do not exist are left for the file service to report.
+fn is_within_root(root: impl AsRef<Path>, path: impl AsRef<Path>) -> bool {
+    match (root.as_ref().canonicalize(), path.as_ref().canonicalize()) {
+        (Ok(root), Ok(path)) => path.starts_with(root),
+        (Ok(_), Err(_)) => true,
+        (Err(_), _) => false,
+    }
+}
+
+fn original_path(req: &Request) -> &str {
+    if let Some(path) = req.extensions().get::<OriginalUri>() {
         path.0.path()
     } else {
         req.uri().path()
-    };
+    }
+}
+
+fn check_path_suffix(req: &Request) -> Option<Response> {
+    let original_uri = original_path(req);
     if !original_uri.ends_with('/') {
         Some(Redirect::permanent(add_root_suffix(original_uri).as_str()).into_response())
     } else {
@@ -156,10 +891,22 @@ fn check_path_suffix(req: &Request) -> Option<Response> {
 }
 
 // Generate a directory file list
-async fn get_dir_list(local_path: impl AsRef<Path>) -> Result<DirList> {
+// Entries are filtered by a case-insensitive substring of their name if `filter` is given.
+async fn get_dir_list(
+    local_path: impl AsRef<Path>,
+    state: &HttpServeState,
+    filter: Option<&str>,
+) -> Result<DirList> {
+    let filter = filter.filter(|f| !f.is_empty()).map(|f| f.to_lowercase());
     let mut entries = fs::read_dir(local_path).await?;
     let mut df_entries = Vec::new();
     while let Some(entry) = entries.next_entry().await? {
+        if !state.follow_symlinks
+            && entry.file_type().await?.is_symlink()
+            && !is_within_root(&state.path, entry.path())
+        {
+            continue;
+        }
         let etype = if entry.path().is_dir() {
             "folder".to_string()
         } else {
@@ -177,10 +924,18 @@ async fn get_dir_list(local_path: impl AsRef<Path>) -> Result<DirList> {
             }
             None => continue,
         };
+        if is_excluded(state, &entry.path()) {
+            continue;
+        }
+        if let Some(filter) = &filter {
+            if !name.to_lowercase().contains(filter) {
+                continue;
+            }
+        }
         let path = name.clone();
         let icon = format!("{}.gif", etype);
-        let date_time: DateTime<Utc> = entry.metadata().await?.modified()?.into();
-        let update = date_time.format("%Y-%m-%d %H:%M").to_string();
+        let modified: DateTime<Utc> = entry.metadata().await?.modified()?.into();
+        let update = modified.format("%Y-%m-%d %H:%M").to_string();
         let size_bytes = entry.metadata().await?.len();
         let size = match etype.as_str() {
             "folder" => "-".to_string(),
@@ -201,13 +956,37 @@ async fn get_dir_list(local_path: impl AsRef<Path>) -> Result<DirList> {
             icon,
             update,
             size,
+            len: size_bytes,
+            modified,
         });
     }
+    df_entries.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(DirList {
         entries: df_entries,
     })
 }
 
+// Split the request path into links to the root and each ancestor directory.
+fn build_breadcrumbs(req_path: &str) -> Vec<Breadcrumb> {
+    let mut breadcrumbs = vec![Breadcrumb {
+        name: "root".to_string(),
+        path: "/".to_string(),
+    }];
+    let mut path = String::from("/");
+    for segment in req_path.split('/').filter(|s| !s.is_empty()) {
+        path.push_str(segment);
+        path.push('/');
+        let name = percent_decode(segment.as_bytes())
+            .decode_utf8_lossy()
+            .to_string();
+        breadcrumbs.push(Breadcrumb {
+            name,
+            path: path.clone(),
+        });
+    }
+    breadcrumbs
+}
+
 fn add_root_suffix(path: &str) -> String {
     if path.is_empty() {
         "/".to_string()
@@ -218,10 +997,21 @@ fn add_root_suffix(path: &str) -> String {
 
 const INDEX_HTML: &str = include_str!("../../templates/index.html");
 
-fn render_template(data: impl Serialize) -> Result<String> {
+// The listing template given with --template, checked up front so a typo
+// fails at startup rather than on every request.
+fn load_template(path: &Path) -> Result<String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read template {}: {}", path.display(), e))?;
+    Environment::new()
+        .add_template("index.html", &source)
+        .map_err(|e| anyhow!("Invalid template {}: {}", path.display(), e))?;
+    Ok(source)
+}
+
+fn render_template(data: impl Serialize, template: Option<&str>) -> Result<String> {
     let mut env = Environment::new();
     let temp_name = "index.html";
-    env.add_template(temp_name, INDEX_HTML)?;
+    env.add_template(temp_name, template.unwrap_or(INDEX_HTML))?;
     let tmpl = env.get_template(temp_name)?;
     let rendered = tmpl.render(data)?;
     Ok(rendered)
@@ -230,8 +1020,28 @@ fn render_template(data: impl Serialize) -> Result<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use axum::http::{Request, Uri};
+    use axum::http::{Method, Request, Uri};
     use std::str::FromStr;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_browser_url() {
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080);
+        assert_eq!(browser_url(addr), "http://127.0.0.1:8080/");
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 3000);
+        assert_eq!(browser_url(addr), "http://127.0.0.1:3000/");
+        let addr: SocketAddr = "[::]:8080".parse().unwrap();
+        assert_eq!(browser_url(addr), "http://[::1]:8080/");
+        let addr: SocketAddr = "192.168.1.2:80".parse().unwrap();
+        assert_eq!(browser_url(addr), "http://192.168.1.2:80/");
+    }
+
+    #[test]
+    fn test_render_qr() {
+        let qr = render_qr("http://192.168.1.2:8080/").unwrap();
+        assert!(!qr.is_empty());
+        assert!(qr.lines().count() > 10);
+    }
 
     #[test]
     fn test_add_root_suffix() {
@@ -243,7 +1053,11 @@ mod tests {
     #[tokio::test]
     async fn test_get_dir_list() {
         let local_path = PathBuf::from("src");
-        let result = get_dir_list(local_path).await;
+        let state = HttpServeState {
+            path: local_path.clone(),
+            ..Default::default()
+        };
+        let result = get_dir_list(local_path, &state, None).await;
         assert!(result.is_ok());
         let mut dir_list = result.unwrap();
         dir_list.entries.sort_by(|a, b| a.name.cmp(&b.name));
@@ -252,10 +1066,42 @@ mod tests {
         assert_eq!(dir_list.entries[0].name, "cli/");
     }
 
+    #[tokio::test]
+    async fn test_get_dir_list_filter() {
+        let state = HttpServeState {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        };
+        let dir_list = get_dir_list("src", &state, Some("CLI")).await.unwrap();
+        assert_eq!(dir_list.entries.len(), 1);
+        assert_eq!(dir_list.entries[0].name, "cli/");
+    }
+
+    #[tokio::test]
+    async fn test_file_service_query() {
+        let state = Arc::new(HttpServeState {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/?q=cli").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(">cli&#x2f;</a>"));
+        assert!(!body.contains(">lib.rs</a>"));
+    }
+
     #[tokio::test]
     async fn test_file_service() {
         let state = Arc::new(HttpServeState {
             path: PathBuf::from("src"),
+            ..Default::default()
         });
         let req = Request::builder()
             .uri(Uri::from_str("/lib.rs").unwrap())
@@ -266,11 +1112,476 @@ mod tests {
         assert_eq!(res.headers().get("content-type").unwrap(), "text/x-rust");
     }
 
+    #[tokio::test]
+    async fn test_serve_single_file() {
+        let app = build_app(HttpServeConfig {
+            path: PathBuf::from("Cargo.toml"),
+            ..Default::default()
+        })
+        .unwrap();
+        let req = Request::builder()
+            .uri("/")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("content-type").unwrap(), "text/x-toml");
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], std::fs::read("Cargo.toml").unwrap().as_slice());
+
+        let app = build_app(HttpServeConfig {
+            path: PathBuf::from("Cargo.toml"),
+            force_download: true,
+            headers: vec!["X-Frame-Options: DENY".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let req = Request::builder()
+            .uri("/")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(
+            res.headers().get("content-disposition").unwrap(),
+            r#"attachment; filename="Cargo.toml""#
+        );
+        assert_eq!(res.headers().get("x-frame-options").unwrap(), "DENY");
+    }
+
+    #[tokio::test]
+    async fn test_exclude_patterns() {
+        let mut exclude = GlobSetBuilder::new();
+        exclude.add(Glob::new("*.key").unwrap());
+        exclude.add(Glob::new("cli").unwrap());
+        let state = Arc::new(HttpServeState {
+            path: PathBuf::from("src"),
+            exclude: exclude.build().unwrap(),
+            ..Default::default()
+        });
+        let dir_list = get_dir_list("src", &state, None).await.unwrap();
+        assert!(dir_list.entries.iter().all(|e| e.name != "cli/"));
+        assert!(dir_list.entries.iter().any(|e| e.name == "lib.rs"));
+
+        let req = Request::builder()
+            .uri(Uri::from_str("/cli/mod.rs").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state.clone()), req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(is_excluded(&state, Path::new("src/keys/secret.key")));
+
+        let req = Request::builder()
+            .method(Method::from_bytes(b"PROPFIND").unwrap())
+            .uri(Uri::from_str("/cli/").unwrap())
+            .header("depth", "0")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = webdav::webdav_service(State(state.clone()), req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_file_service_index() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("index.html"), "<h1>home</h1>")
+            .await
+            .unwrap();
+        let state = Arc::new(HttpServeState {
+            path: root.path().to_path_buf(),
+            index: "index.html".to_string(),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("content-type").unwrap(), "text/html");
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"<h1>home</h1>");
+    }
+
+    #[tokio::test]
+    async fn test_file_service_spa_fallback() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("index.html"), "<div id=app></div>")
+            .await
+            .unwrap();
+        let state = Arc::new(HttpServeState {
+            path: root.path().to_path_buf(),
+            index: "index.html".to_string(),
+            spa: true,
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/some/deep/route").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"<div id=app></div>");
+    }
+
+    #[tokio::test]
+    async fn test_health_check() {
+        let app = build_app(HttpServeConfig {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        })
+        .unwrap();
+        let req = Request::builder()
+            .uri("/healthz")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let health: HealthStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health.status, "ok");
+        assert!(health.uptime_secs >= 0);
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        async fn requests_ok(app: &Router) -> u64 {
+            let req = Request::builder()
+                .uri("/metrics")
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let res = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            String::from_utf8(body.to_vec())
+                .unwrap()
+                .lines()
+                .find(|l| l.starts_with("http_requests_total{") && l.contains(r#"status="200""#))
+                .and_then(|l| l.split_whitespace().last())
+                .map(|v| v.parse().unwrap())
+                .unwrap_or(0)
+        }
+
+        let app = build_app(HttpServeConfig {
+            path: PathBuf::from("src"),
+            metrics: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let before = requests_ok(&app).await;
+        let req = Request::builder()
+            .uri("/lib.rs")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let after = requests_ok(&app).await;
+        assert!(after > before);
+    }
+
+    #[tokio::test]
+    async fn test_max_body() {
+        let app = build_app(HttpServeConfig {
+            path: PathBuf::from("src"),
+            max_body: Some(16),
+            ..Default::default()
+        })
+        .unwrap();
+        let req = Request::builder()
+            .method("PUT")
+            .uri("/upload.txt")
+            .header("content-length", "32")
+            .body(axum::body::Body::from(vec![b'a'; 32]))
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let (name, value) = parse_header("Cache-Control: max-age=3600").unwrap();
+        assert_eq!(name, header::CACHE_CONTROL);
+        assert_eq!(value, "max-age=3600");
+        assert!(parse_header("Cache-Control max-age=3600").is_err());
+        assert!(parse_header("Bad Name: value").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_headers() {
+        let app = build_app(HttpServeConfig {
+            path: PathBuf::from("src"),
+            headers: vec!["X-Frame-Options: DENY".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let req = Request::builder()
+            .uri("/lib.rs")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("x-frame-options").unwrap(), "DENY");
+    }
+
+    #[tokio::test]
+    async fn test_force_download() {
+        let state = Arc::new(HttpServeState {
+            path: PathBuf::from("src"),
+            force_download: true,
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/lib.rs").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get("content-disposition").unwrap(),
+            r#"attachment; filename="lib.rs""#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_query() {
+        let state = Arc::new(HttpServeState {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/lib.rs?dl").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state.clone()), req).await;
+        assert!(res.headers().contains_key("content-disposition"));
+
+        let req = Request::builder()
+            .uri(Uri::from_str("/lib.rs").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        assert!(!res.headers().contains_key("content-disposition"));
+    }
+
+    #[test]
+    fn test_content_disposition_non_ascii() {
+        let value = content_disposition("报告.pdf").unwrap();
+        assert_eq!(
+            value,
+            "attachment; filename=\"__.pdf\"; filename*=UTF-8''%E6%8A%A5%E5%91%8A%2Epdf"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_service_not_modified() {
+        let state = Arc::new(HttpServeState {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/lib.rs").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state.clone()), req).await;
+        assert!(res.headers().contains_key("last-modified"));
+
+        let future = Utc::now() + chrono::Duration::days(1);
+        let req = Request::builder()
+            .uri(Uri::from_str("/lib.rs").unwrap())
+            .header(
+                "if-modified-since",
+                future.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+            )
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mime_override() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("app.log"), "started")
+            .await
+            .unwrap();
+        let state = Arc::new(HttpServeState {
+            path: root.path().to_path_buf(),
+            mime: HashMap::from([parse_mime(".log=text/plain").unwrap()]),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/app.log").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("content-type").unwrap(), "text/plain");
+        assert!(parse_mime("log").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_paginate() {
+        let state = HttpServeState {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        };
+        let dir_list = get_dir_list("src", &state, None).await.unwrap();
+        let (entries, pagination) = paginate(dir_list.entries, 2, 2);
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["main.rs", "process/"]);
+        assert_eq!(pagination.page, 2);
+        assert_eq!(pagination.pages, 3);
+        let params = list_params(Some("a b"), SortKey::Name, SortOrder::Asc);
+        let pagination = pagination.with_links(2, &params);
+        assert_eq!(pagination.prev.unwrap(), "?page=1&per_page=2&q=a%20b");
+        assert_eq!(pagination.next.unwrap(), "?page=3&per_page=2&q=a%20b");
+    }
+
+    #[tokio::test]
+    async fn test_file_service_page() {
+        let state = Arc::new(HttpServeState {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/?page=2&per_page=2").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(">main.rs</a>"));
+        assert!(body.contains(">process&#x2f;</a>"));
+        assert!(!body.contains(">lib.rs</a>"));
+        assert!(body.contains("Page 2 of 3"));
+    }
+
+    #[tokio::test]
+    async fn test_file_service_precompressed() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("app.js"), "console.log(1);").unwrap();
+        std::fs::write(root.path().join("app.js.gz"), "gzipped").unwrap();
+        let state = Arc::new(HttpServeState {
+            path: root.path().to_path_buf(),
+            precompressed: true,
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri("/app.js")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state.clone()), req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "application/javascript"
+        );
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"gzipped");
+
+        let req = Request::builder()
+            .uri("/app.js")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+    }
+
+    #[tokio::test]
+    async fn test_file_service_sort() {
+        let state = Arc::new(HttpServeState {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/?sort=name&order=desc").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        // The active column links to the opposite order and shows the current one.
+        assert!(body.contains(r#"<a href="?sort=name&amp;order=asc">Name</a> ▼"#));
+        assert!(body.contains(r#"<a href="?sort=size&amp;order=asc">Size</a>"#));
+        let utils = body.find(">utils&#x2f;</a>").unwrap();
+        let cli = body.find(">cli&#x2f;</a>").unwrap();
+        assert!(utils < cli);
+    }
+
+    #[tokio::test]
+    async fn test_file_service_preview() {
+        let state = Arc::new(HttpServeState {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/lib.rs?preview").unwrap())
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get("content-type").unwrap(),
+            "text/html; charset=utf-8"
+        );
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("<span style="));
+    }
+
+    #[tokio::test]
+    async fn test_file_service_range() {
+        let state = Arc::new(HttpServeState {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .uri(Uri::from_str("/lib.rs").unwrap())
+            .header("range", "bytes=0-3")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = file_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        let len = fs::metadata("src/lib.rs").await.unwrap().len();
+        assert_eq!(
+            res.headers().get("content-range").unwrap(),
+            format!("bytes 0-3/{}", len).as_str()
+        );
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"mod ");
+    }
+
     #[test]
     fn test_build_and_validate_path() {
         let base_path = PathBuf::from("src");
         let req_path = "/lib.rs";
-        let result = build_and_validate_path(base_path, req_path);
+        let result = build_and_validate_path(base_path, req_path, false);
         assert!(result.is_some());
         let path = result.unwrap();
         assert_eq!(path, PathBuf::from("src/lib.rs"));
@@ -280,8 +1591,31 @@ mod tests {
     fn test_build_and_validate_path_invalid() {
         let base_path = PathBuf::from("src");
         let req_path = "/../lib.rs";
-        let result = build_and_validate_path(base_path, req_path);
+        let result = build_and_validate_path(base_path, req_path, false);
+        assert!(result.is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_outside_root() {
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("secret.txt"), "secret")
+            .await
+            .unwrap();
+        let root = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("link")).unwrap();
+
+        let result = build_and_validate_path(root.path(), "/link/secret.txt", false);
         assert!(result.is_none());
+        let result = build_and_validate_path(root.path(), "/link/secret.txt", true);
+        assert!(result.is_some());
+
+        let state = HttpServeState {
+            path: root.path().to_path_buf(),
+            ..Default::default()
+        };
+        let dir_list = get_dir_list(root.path(), &state, None).await.unwrap();
+        assert!(dir_list.entries.is_empty());
     }
 
     #[test]
@@ -306,9 +1640,56 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_build_breadcrumbs() {
+        let result = build_breadcrumbs("/sub/my%20dir/");
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].path, "/");
+        assert_eq!(result[1].name, "sub");
+        assert_eq!(result[1].path, "/sub/");
+        assert_eq!(result[2].name, "my dir");
+        assert_eq!(result[2].path, "/sub/my%20dir/");
+    }
+
+    #[test]
+    fn test_render_template_breadcrumbs() {
+        let data = IndexPage {
+            breadcrumbs: build_breadcrumbs("/sub/dir/"),
+            entries: vec![],
+            pagination: paginate(vec![], 1, DEFAULT_PER_PAGE).1,
+            columns: sort_links(None, SortKey::Name, SortOrder::Asc),
+            query: String::new(),
+            theme: "auto".to_string(),
+        };
+        let rendered = render_template(data, None).unwrap();
+        // minijinja escapes '/' in html templates
+        assert!(rendered.contains(r#"<a href="&#x2f;">root</a>"#));
+        assert!(rendered.contains(r#"<a href="&#x2f;sub&#x2f;">sub</a>"#));
+        assert!(rendered.contains(r#"<a href="&#x2f;sub&#x2f;dir&#x2f;">dir</a>"#));
+    }
+
+    #[test]
+    fn test_render_template_dark_theme() {
+        let data = IndexPage {
+            breadcrumbs: build_breadcrumbs("/"),
+            entries: vec![],
+            pagination: paginate(vec![], 1, DEFAULT_PER_PAGE).1,
+            columns: sort_links(None, SortKey::Name, SortOrder::Asc),
+            query: String::new(),
+            theme: "dark".to_string(),
+        };
+        let rendered = render_template(data, None).unwrap();
+        assert!(rendered.contains(r#"<html lang="en" data-theme="dark">"#));
+    }
+
     #[test]
     fn test_render_template() {
-        let data = DirList {
+        let data = IndexPage {
+            breadcrumbs: build_breadcrumbs("/"),
+            pagination: paginate(vec![], 1, DEFAULT_PER_PAGE).1,
+            columns: sort_links(None, SortKey::Name, SortOrder::Asc),
+            query: String::new(),
+            theme: "auto".to_string(),
             entries: vec![DirEntry {
                 path: "src/".to_string(),
                 name: "src/".to_string(),
@@ -316,9 +1697,85 @@ mod tests {
                 icon: "/folder.gif".to_string(),
                 update: "2021-09-01 00:00".to_string(),
                 size: "-".to_string(),
+                len: 0,
+                modified: Utc::now(),
             }],
         };
-        let result = render_template(data);
+        let result = render_template(data, None);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_render_custom_template() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            file.path(),
+            "<ul class=\"custom\">{% for e in entries %}<li>{{ e.name }}</li>{% endfor %}</ul>\
+             {% for b in breadcrumbs %}[{{ b.name }}]{% endfor %}",
+        )
+        .unwrap();
+        let template = load_template(file.path()).unwrap();
+        let data = IndexPage {
+            breadcrumbs: build_breadcrumbs("/sub/"),
+            pagination: paginate(vec![], 1, DEFAULT_PER_PAGE).1,
+            columns: sort_links(None, SortKey::Name, SortOrder::Asc),
+            query: String::new(),
+            theme: "auto".to_string(),
+            entries: vec![DirEntry {
+                path: "a.txt".to_string(),
+                name: "a.txt".to_string(),
+                etype: "file".to_string(),
+                icon: "/file.gif".to_string(),
+                update: "2021-09-01 00:00".to_string(),
+                size: "1 B".to_string(),
+                len: 1,
+                modified: Utc::now(),
+            }],
+        };
+        let rendered = render_template(data, Some(&template)).unwrap();
+        assert_eq!(
+            rendered,
+            r#"<ul class="custom"><li>a.txt</li></ul>[root][sub]"#
+        );
+
+        std::fs::write(file.path(), "{% for e in entries %}").unwrap();
+        assert!(load_template(file.path()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let app: Router = Router::new().route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                "done"
+            }),
+        );
+        let app = with_request_timeout(app, Duration::from_millis(50));
+        let req = Request::builder()
+            .uri("/slow")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::REQUEST_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_skips_uploads() {
+        let app: Router = Router::new().route(
+            "/slow",
+            axum::routing::put(|| async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                StatusCode::CREATED
+            }),
+        );
+        let app = with_request_timeout(app, Duration::from_millis(50));
+        let req = Request::builder()
+            .method("PUT")
+            .uri("/slow")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+    }
 }
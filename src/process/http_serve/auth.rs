@@ -0,0 +1,191 @@
+use super::HttpServeState;
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use percent_encoding::percent_decode;
+use std::sync::Arc;
+use tracing::debug;
+
+#[derive(Debug, PartialEq)]
+pub(super) struct ProtectRule {
+    segments: Vec<String>,
+    user: String,
+    pass: String,
+}
+
+impl ProtectRule {
+    // Parse a `/prefix:user:pass` rule, the password may contain ':'.
+    pub(super) fn parse(rule: &str) -> Result<Self> {
+        let err = || {
+            anyhow!(
+                "invalid protect rule `{}`, expected `/path:user:pass`",
+                rule
+            )
+        };
+        let (prefix, credentials) = rule.split_once(':').ok_or_else(err)?;
+        let (user, pass) = credentials.split_once(':').ok_or_else(err)?;
+        if !prefix.starts_with('/') || user.is_empty() {
+            return Err(err());
+        }
+        Ok(Self {
+            segments: path_segments(prefix),
+            user: user.to_string(),
+            pass: pass.to_string(),
+        })
+    }
+
+    fn matches(&self, segments: &[String]) -> bool {
+        segments.starts_with(&self.segments)
+    }
+
+    fn authorized(&self, authorization: Option<&str>) -> bool {
+        let decoded = authorization
+            .and_then(|v| v.strip_prefix("Basic "))
+            .and_then(|v| STANDARD.decode(v.trim()).ok())
+            .and_then(|v| String::from_utf8(v).ok());
+        match decoded.as_deref().and_then(|v| v.split_once(':')) {
+            Some((user, pass)) => user == self.user && pass == self.pass,
+            None => false,
+        }
+    }
+}
+
+// Compare decoded segments so that `/priv%61te` or `//private` can't bypass a rule.
+fn path_segments(path: &str) -> Vec<String> {
+    let decoded = percent_decode(path.as_bytes()).decode_utf8_lossy();
+    decoded
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .map(|s| s.to_string())
+        .collect()
+}
+
+pub(super) async fn protect_paths(
+    State(state): State<Arc<HttpServeState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !authorized(&state, req.uri().path(), authorization(&req)) {
+        debug!("Unauthorized request: {}", req.uri().path());
+        return unauthorized();
+    }
+    next.run(req).await
+}
+
+// Whether the credentials pass the rule protecting `path`, if any.
+pub(super) fn authorized(state: &HttpServeState, path: &str, authorization: Option<&str>) -> bool {
+    let segments = path_segments(path);
+    // The most specific rule wins when prefixes are nested.
+    let rule = state
+        .protect
+        .iter()
+        .filter(|rule| rule.matches(&segments))
+        .max_by_key(|rule| rule.segments.len());
+    rule.is_none_or(|rule| rule.authorized(authorization))
+}
+
+pub(super) fn authorization(req: &Request) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+}
+
+pub(super) fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, r#"Basic realm="rcli""#)],
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::{build_app, HttpServeConfig};
+    use axum::body::Body;
+    use std::path::PathBuf;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_parse_protect_rule() {
+        let rule = ProtectRule::parse("/private/docs:user:pa:ss").unwrap();
+        assert_eq!(rule.segments, ["private", "docs"]);
+        assert_eq!(rule.user, "user");
+        assert_eq!(rule.pass, "pa:ss");
+        assert!(ProtectRule::parse("private:user:pass").is_err());
+        assert!(ProtectRule::parse("/private:user").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_protect_paths() {
+        let app = build_app(HttpServeConfig {
+            path: PathBuf::from("src"),
+            protect: vec!["/process:user:pass".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        for uri in ["/process/", "/process/mod.rs", "/proc%65ss/", "//process/"] {
+            let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let res = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+            assert!(res.headers().contains_key("www-authenticate"));
+        }
+
+        let req = Request::builder()
+            .uri("/process/")
+            .header(
+                "authorization",
+                format!("Basic {}", STANDARD.encode("user:pass")),
+            )
+            .body(Body::empty())
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = Request::builder()
+            .uri("/process/")
+            .header(
+                "authorization",
+                format!("Basic {}", STANDARD.encode("user:bad")),
+            )
+            .body(Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_protect_single_file() {
+        let app = build_app(HttpServeConfig {
+            path: PathBuf::from("Cargo.toml"),
+            protect: vec!["/:user:pass".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let req = Request::builder()
+            .uri("/")
+            .header(
+                "authorization",
+                format!("Basic {}", STANDARD.encode("user:pass")),
+            )
+            .body(Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::Result;
+use axum::{body::Body, extract::ConnectInfo, Router};
+use hyper::{body::Incoming, service::service_fn};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::conn::auto,
+};
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpListener, TcpStream},
+};
+use tower::ServiceExt;
+use tracing::{debug, error};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct ConnOptions {
+    pub(super) http2: bool,
+    // Seconds an idle connection is kept open, 0 disables keep-alive.
+    pub(super) keep_alive: u64,
+    // Seconds a client has to send the request headers, 0 means no limit.
+    pub(super) request_timeout: u64,
+}
+
+// Accept connections by hand so HTTP/2 over cleartext (h2c) can be turned on,
+// by default only HTTP/1.1 is spoken.
+pub(super) async fn serve(listener: TcpListener, app: Router, opts: ConnOptions) -> Result<()> {
+    loop {
+        let (stream, remote) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Error accepting connection: {:?}", e);
+                continue;
+            }
+        };
+        let app = app.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |mut req: hyper::Request<Incoming>| {
+                req.extensions_mut().insert(ConnectInfo(remote));
+                app.clone().oneshot(req.map(Body::new))
+            });
+            let mut builder = auto::Builder::new(TokioExecutor::new());
+            if !opts.http2 {
+                builder = builder.http1_only();
+            }
+            let mut http1 = builder.http1();
+            http1
+                .timer(TokioTimer::new())
+                .keep_alive(opts.keep_alive > 0);
+            if opts.request_timeout > 0 {
+                http1.header_read_timeout(Duration::from_secs(opts.request_timeout));
+            }
+
+            let stream = IdleStream::new(stream);
+            let last_active = stream.last_active.clone();
+            let conn = builder.serve_connection(TokioIo::new(stream), service);
+            tokio::pin!(conn);
+            let result = if opts.keep_alive > 0 {
+                let idle = Duration::from_secs(opts.keep_alive);
+                tokio::select! {
+                    result = conn.as_mut() => result,
+                    _ = last_active.idle_for(idle) => {
+                        debug!("Closing idle connection from {}", remote);
+                        conn.as_mut().graceful_shutdown();
+                        conn.await
+                    }
+                }
+            } else {
+                conn.await
+            };
+            if let Err(e) = result {
+                debug!("Connection from {} closed: {:?}", remote, e);
+            }
+        });
+    }
+}
+
+// The last time any bytes were read or written on a connection.
+#[derive(Debug, Clone)]
+struct LastActive {
+    started: Instant,
+    millis: Arc<AtomicU64>,
+}
+
+impl LastActive {
+    fn touch(&self) {
+        let elapsed = self.started.elapsed().as_millis() as u64;
+        self.millis.store(elapsed, Ordering::Relaxed);
+    }
+
+    fn idle(&self) -> Duration {
+        let active = Duration::from_millis(self.millis.load(Ordering::Relaxed));
+        self.started.elapsed().saturating_sub(active)
+    }
+
+    // Resolve once the connection has been quiet for the given duration.
+    async fn idle_for(&self, timeout: Duration) {
+        loop {
+            let idle = self.idle();
+            if idle >= timeout {
+                return;
+            }
+            tokio::time::sleep(timeout - idle).await;
+        }
+    }
+}
+
+struct IdleStream {
+    inner: TcpStream,
+    last_active: LastActive,
+}
+
+impl IdleStream {
+    fn new(inner: TcpStream) -> Self {
+        Self {
+            inner,
+            last_active: LastActive {
+                started: Instant::now(),
+                millis: Arc::new(AtomicU64::new(0)),
+            },
+        }
+    }
+}
+
+impl AsyncRead for IdleStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let res = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if res.is_ready() {
+            this.last_active.touch();
+        }
+        res
+    }
+}
+
+impl AsyncWrite for IdleStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let res = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if res.is_ready() {
+            this.last_active.touch();
+        }
+        res
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::{build_app, HttpServeConfig};
+    use axum::http::{Request, StatusCode, Version};
+    use std::path::PathBuf;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn start(opts: ConnOptions) -> Result<std::net::SocketAddr> {
+        let app = build_app(HttpServeConfig {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        })?;
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(serve(listener, app, opts));
+        Ok(addr)
+    }
+
+    async fn http2_get(http2: bool) -> Result<Version> {
+        let addr = start(ConnOptions {
+            http2,
+            ..Default::default()
+        })
+        .await?;
+        let stream = TcpStream::connect(addr).await?;
+        let (mut sender, conn) =
+            hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+                .await?;
+        tokio::spawn(conn);
+        let req = Request::builder()
+            .uri(format!("http://{}/healthz", addr))
+            .body(Body::empty())?;
+        let res = sender.send_request(req).await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        Ok(res.version())
+    }
+
+    #[tokio::test]
+    async fn test_serve_http2() {
+        let version = http2_get(true).await.unwrap();
+        assert_eq!(version, Version::HTTP_2);
+        assert!(http2_get(false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_idle_connection_closed() {
+        let addr = start(ConnOptions {
+            keep_alive: 1,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /healthz HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        // The server closes the connection once it's been idle for a second.
+        let mut buf = Vec::new();
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut buf));
+        read.await.unwrap().unwrap();
+        assert!(buf.starts_with(b"HTTP/1.1 200 OK"));
+    }
+}
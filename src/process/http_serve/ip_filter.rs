@@ -0,0 +1,88 @@
+use super::HttpServeState;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use ipnet::IpNet;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use tracing::debug;
+
+// A denied network always wins, otherwise the peer must be allowed if there is any allow rule.
+fn is_allowed(ip: IpAddr, allow: &[IpNet], deny: &[IpNet]) -> bool {
+    let ip = ip.to_canonical();
+    if deny.iter().any(|net| net.contains(&ip)) {
+        return false;
+    }
+    allow.is_empty() || allow.iter().any(|net| net.contains(&ip))
+}
+
+pub(super) async fn filter_ips(
+    State(state): State<Arc<HttpServeState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    match peer {
+        Some(ip) if is_allowed(ip, &state.allow, &state.deny) => next.run(req).await,
+        _ => {
+            debug!("Forbidden client: {:?}", peer);
+            StatusCode::FORBIDDEN.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::{build_app, HttpServeConfig};
+    use axum::body::Body;
+    use std::path::PathBuf;
+    use tower::ServiceExt;
+
+    fn nets(cidrs: &[&str]) -> Vec<IpNet> {
+        cidrs.iter().map(|c| c.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        let allow = nets(&["192.168.0.0/16"]);
+        let deny = nets(&["192.168.1.0/24", "10.0.0.0/8"]);
+        assert!(is_allowed("192.168.2.1".parse().unwrap(), &allow, &deny));
+        assert!(!is_allowed("192.168.1.1".parse().unwrap(), &allow, &deny));
+        assert!(!is_allowed("172.16.0.1".parse().unwrap(), &allow, &deny));
+        assert!(!is_allowed("10.1.2.3".parse().unwrap(), &[], &deny));
+        assert!(is_allowed("172.16.0.1".parse().unwrap(), &[], &deny));
+        // IPv4-mapped IPv6 peers are matched as IPv4.
+        assert!(!is_allowed("::ffff:10.1.2.3".parse().unwrap(), &[], &deny));
+    }
+
+    #[tokio::test]
+    async fn test_filter_ips() {
+        let app = build_app(HttpServeConfig {
+            path: PathBuf::from("src"),
+            allow: vec!["192.168.0.0/16".to_string()],
+            deny: vec!["10.0.0.0/8".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        for (peer, status) in [
+            ("192.168.1.10:4000", StatusCode::OK),
+            ("10.0.0.1:4000", StatusCode::FORBIDDEN),
+            ("172.16.0.1:4000", StatusCode::FORBIDDEN),
+        ] {
+            let mut req = Request::builder().uri("/").body(Body::empty()).unwrap();
+            let addr: SocketAddr = peer.parse().unwrap();
+            req.extensions_mut().insert(ConnectInfo(addr));
+            let res = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(res.status(), status, "{}", peer);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use minijinja::{context, Environment};
+use std::{fs::File, io::Read, path::Path, sync::OnceLock};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Color, Theme, ThemeSet},
+    html::{styled_line_to_highlighted_html, IncludeBackground},
+    parsing::SyntaxSet,
+};
+
+// Larger files are truncated to keep the page responsive.
+const MAX_PREVIEW_BYTES: u64 = 512 * 1024;
+
+const PREVIEW_HTML: &str = include_str!("../../../templates/preview.html");
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_nonewlines)
+}
+
+fn theme(name: &str) -> &'static Theme {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    let themes = THEME_SET.get_or_init(ThemeSet::load_defaults);
+    let key = match name {
+        "dark" => "base16-ocean.dark",
+        _ => "InspiredGitHub",
+    };
+    &themes.themes[key]
+}
+
+// Render the file as a highlighted HTML page, or None if its type isn't known text.
+pub(super) fn render_preview(path: &Path, theme_name: &str) -> Result<Option<String>> {
+    let ss = syntax_set();
+    let Some(syntax) = path
+        .extension()
+        .and_then(|ext| ss.find_syntax_by_extension(&ext.to_string_lossy()))
+    else {
+        return Ok(None);
+    };
+
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut buf = Vec::new();
+    file.take(MAX_PREVIEW_BYTES).read_to_end(&mut buf)?;
+    let truncated = size > MAX_PREVIEW_BYTES;
+    let mut text = String::from_utf8_lossy(&buf).to_string();
+    if truncated {
+        // Drop the last partial line.
+        if let Some(pos) = text.rfind('\n') {
+            text.truncate(pos);
+        }
+    }
+
+    let theme = theme(theme_name);
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let regions = highlighter.highlight_line(line, ss)?;
+        lines.push(styled_line_to_highlighted_html(
+            &regions,
+            IncludeBackground::No,
+        )?);
+    }
+
+    let mut env = Environment::new();
+    env.add_template("preview.html", PREVIEW_HTML)?;
+    let tmpl = env.get_template("preview.html")?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let rendered = tmpl.render(context! {
+        name,
+        lines,
+        truncated,
+        shown => text.len(),
+        size,
+        background => css_color(theme.settings.background, "#ffffff"),
+        foreground => css_color(theme.settings.foreground, "#000000"),
+    })?;
+    Ok(Some(rendered))
+}
+
+fn css_color(color: Option<Color>, default: &str) -> String {
+    match color {
+        Some(c) => format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b),
+        None => default.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_preview_rust() {
+        let html = render_preview(Path::new("src/lib.rs"), "auto")
+            .unwrap()
+            .unwrap();
+        assert!(html.contains("<span"));
+        assert!(html.contains(r#"<span class="line-number">1</span>"#));
+        assert!(!html.contains("too large"));
+    }
+
+    #[test]
+    fn test_render_preview_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("big.txt");
+        let line = "a line of plain text\n";
+        let content = line.repeat(MAX_PREVIEW_BYTES as usize / line.len() + 10);
+        std::fs::write(&path, content).unwrap();
+        let html = render_preview(&path, "dark").unwrap().unwrap();
+        assert!(html.contains("The file is too large"));
+    }
+
+    #[test]
+    fn test_render_preview_unknown() {
+        let result = render_preview(Path::new("assets/encode-png.b64"), "auto").unwrap();
+        assert!(result.is_none());
+    }
+}
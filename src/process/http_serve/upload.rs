@@ -0,0 +1,163 @@
+use super::{build_and_validate_path, is_excluded, is_within_root, original_path, HttpServeState};
+use anyhow::Result;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use futures::TryStreamExt;
+use std::{path::Path, sync::Arc};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{debug, error};
+
+// Create or replace a file with the request body.
+pub(super) async fn put_file(State(state): State<Arc<HttpServeState>>, req: Request) -> Response {
+    let Some(file_path) =
+        build_and_validate_path(&state.path, original_path(&req), state.follow_symlinks)
+    else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    if is_excluded(&state, &file_path) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    if file_path.is_dir() {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+    // The file doesn't exist yet, so make sure its directory is inside the root.
+    match file_path.parent() {
+        Some(parent) if parent.is_dir() => {
+            if !state.follow_symlinks && !is_within_root(&state.path, parent) {
+                return StatusCode::BAD_REQUEST.into_response();
+            }
+        }
+        _ => return StatusCode::CONFLICT.into_response(),
+    }
+
+    let existed = file_path.exists();
+    match write_body(&file_path, req.into_body()).await {
+        Ok(_) if existed => StatusCode::NO_CONTENT.into_response(),
+        Ok(_) => StatusCode::CREATED.into_response(),
+        Err(e) => {
+            error!("Error writing file: {:?}", e);
+            // Don't leave a partial upload behind.
+            let _ = fs::remove_file(&file_path).await;
+            StatusCode::BAD_REQUEST.into_response()
+        }
+    }
+}
+
+async fn write_body(path: &Path, body: Body) -> Result<()> {
+    let mut file = fs::File::create(path).await?;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.try_next().await? {
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+// Remove a file, or a directory with everything in it.
+pub(super) async fn delete_path(
+    State(state): State<Arc<HttpServeState>>,
+    req: Request,
+) -> Response {
+    let req_path = original_path(&req);
+    let Some(path) = build_and_validate_path(&state.path, req_path, state.follow_symlinks) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    if is_excluded(&state, &path) || !path.exists() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    if path == state.path {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    debug!("Deleting {:?}", path);
+    let result = if path.is_dir() {
+        fs::remove_dir_all(&path).await
+    } else {
+        fs::remove_file(&path).await
+    };
+    match result {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => {
+            error!("Error deleting {:?}: {:?}", path, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::process::http_serve::{build_app, HttpServeConfig};
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    fn put(uri: &str, body: &'static str) -> Request<Body> {
+        Request::builder()
+            .method(Method::PUT)
+            .uri(uri)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_put_read_only() {
+        let root = tempfile::tempdir().unwrap();
+        let app = build_app(HttpServeConfig {
+            path: root.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+        let res = app.oneshot(put("/hello.txt", "hello")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(!root.path().join("hello.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_put_and_delete_read_write() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("hello.txt");
+        let app = build_app(HttpServeConfig {
+            path: root.path().to_path_buf(),
+            mode: "rw".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let res = app
+            .clone()
+            .oneshot(put("/hello.txt", "hello"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello");
+
+        let res = app
+            .clone()
+            .oneshot(put("/hello.txt", "world"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "world");
+
+        let res = app
+            .clone()
+            .oneshot(put("/missing/hello.txt", ""))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+
+        let req = Request::builder()
+            .method(Method::DELETE)
+            .uri("/hello.txt")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,441 @@
+use super::{
+    auth, build_and_validate_path, get_dir_list, is_excluded, is_within_root, original_path,
+    HttpServeState,
+};
+use anyhow::Result;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use std::{fmt::Write, path::Path, sync::Arc};
+use tokio::fs;
+use tracing::{debug, error};
+
+// Characters that must be encoded in a single path segment of a href.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+const ALLOW_READ: &str = "OPTIONS, GET, HEAD, PROPFIND";
+const ALLOW_WRITE: &str = "OPTIONS, GET, HEAD, PROPFIND, PUT, DELETE, MKCOL, MOVE";
+
+struct DavResource {
+    href: String,
+    name: String,
+    is_dir: bool,
+    len: u64,
+    modified: DateTime<Utc>,
+}
+
+pub(super) async fn webdav_service(
+    State(state): State<Arc<HttpServeState>>,
+    req: Request,
+) -> Response {
+    debug!("Start webdav handler: {}", req.method());
+    let allow = if state.writable {
+        ALLOW_WRITE
+    } else {
+        ALLOW_READ
+    };
+    match req.method().as_str() {
+        "OPTIONS" => (
+            StatusCode::OK,
+            [
+                (header::ALLOW, allow),
+                (header::HeaderName::from_static("dav"), "1"),
+            ],
+        )
+            .into_response(),
+        "PROPFIND" => propfind(&state, req).await,
+        "MKCOL" if state.writable => mkcol(&state, req).await,
+        "MOVE" if state.writable => move_resource(&state, req).await,
+        _ => (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, allow)]).into_response(),
+    }
+}
+
+async fn propfind(state: &HttpServeState, req: Request) -> Response {
+    let req_path = original_path(&req);
+    let Some(file_path) = build_and_validate_path(&state.path, req_path, state.follow_symlinks)
+    else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    // Hidden like in the listings, as if it didn't exist.
+    if is_excluded(state, &file_path) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let metadata = match fs::metadata(&file_path).await {
+        Ok(metadata) => metadata,
+        Err(_) => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    let name = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut href = req_path.to_string();
+    if metadata.is_dir() && !href.ends_with('/') {
+        href.push('/');
+    }
+    let mut resources = vec![DavResource {
+        href: href.clone(),
+        name,
+        is_dir: metadata.is_dir(),
+        len: metadata.len(),
+        modified: metadata.modified().map(Into::into).unwrap_or_default(),
+    }];
+
+    // Depth defaults to infinity, which we treat like 1 to keep responses bounded.
+    let depth = req
+        .headers()
+        .get("depth")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("infinity");
+    if metadata.is_dir() && depth != "0" {
+        match get_dir_list(&file_path, state, None).await {
+            Ok(list) => {
+                for entry in list.entries {
+                    let is_dir = entry.etype == "folder";
+                    let name = entry.name.trim_end_matches('/').to_string();
+                    let mut child = format!("{}{}", href, utf8_percent_encode(&name, SEGMENT));
+                    if is_dir {
+                        child.push('/');
+                    }
+                    resources.push(DavResource {
+                        href: child,
+                        name,
+                        is_dir,
+                        len: entry.len,
+                        modified: entry.modified,
+                    });
+                }
+            }
+            Err(e) => {
+                error!("Error reading directory: {:?}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+
+    match render_multistatus(&resources) {
+        Ok(body) => (
+            StatusCode::MULTI_STATUS,
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/xml; charset=utf-8"),
+            )],
+            body,
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Error rendering multistatus: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn mkcol(state: &HttpServeState, req: Request) -> Response {
+    let Some(dir_path) =
+        build_and_validate_path(&state.path, original_path(&req), state.follow_symlinks)
+    else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    if is_excluded(state, &dir_path) || !parent_within_root(state, &dir_path) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if !auth::authorized(state, req.uri().path(), auth::authorization(&req)) {
+        return auth::unauthorized();
+    }
+    if dir_path.exists() {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+    match fs::create_dir(&dir_path).await {
+        Ok(_) => StatusCode::CREATED.into_response(),
+        // The parent collection does not exist.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => StatusCode::CONFLICT.into_response(),
+        Err(e) => {
+            error!("Error creating directory: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn move_resource(state: &HttpServeState, req: Request) -> Response {
+    let Some(src) =
+        build_and_validate_path(&state.path, original_path(&req), state.follow_symlinks)
+    else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    if !src.exists() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let destination = req
+        .headers()
+        .get("destination")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<Uri>().ok());
+    let Some((dest_uri, dest)) = destination.and_then(|uri| {
+        let dest = build_and_validate_path(&state.path, uri.path(), state.follow_symlinks)?;
+        Some((uri, dest))
+    }) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    // The middleware only sees the source, the destination needs the same checks.
+    if is_excluded(state, &src) || is_excluded(state, &dest) || !parent_within_root(state, &dest) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let authorization = auth::authorization(&req);
+    if !auth::authorized(state, req.uri().path(), authorization)
+        || !auth::authorized(state, dest_uri.path(), authorization)
+    {
+        return auth::unauthorized();
+    }
+    let overwrite = req
+        .headers()
+        .get("overwrite")
+        .map(|v| v.as_bytes() != b"F")
+        .unwrap_or(true);
+    let existed = dest.exists();
+    if existed && !overwrite {
+        return StatusCode::PRECONDITION_FAILED.into_response();
+    }
+    match move_path(&src, &dest, existed).await {
+        Ok(_) if existed => StatusCode::NO_CONTENT.into_response(),
+        Ok(_) => StatusCode::CREATED.into_response(),
+        Err(e) => {
+            error!("Error moving resource: {:?}", e);
+            StatusCode::CONFLICT.into_response()
+        }
+    }
+}
+
+// A path that doesn't exist yet passes `build_and_validate_path`, so make sure
+// its directory is inside the root, as a symlink may lead out of it.
+fn parent_within_root(state: &HttpServeState, path: &Path) -> bool {
+    match path.parent() {
+        Some(parent) if parent.is_dir() => {
+            state.follow_symlinks || is_within_root(&state.path, parent)
+        }
+        // Creating it fails on its own, with a 409.
+        _ => true,
+    }
+}
+
+async fn move_path(src: &Path, dest: &Path, replace: bool) -> Result<()> {
+    if replace && dest.is_dir() {
+        fs::remove_dir_all(dest).await?;
+    }
+    fs::rename(src, dest).await?;
+    Ok(())
+}
+
+fn render_multistatus(resources: &[DavResource]) -> Result<String> {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    xml.push_str(r#"<D:multistatus xmlns:D="DAV:">"#);
+    for res in resources {
+        let modified = res.modified.format("%a, %d %b %Y %H:%M:%S GMT");
+        write!(
+            xml,
+            "<D:response><D:href>{}</D:href><D:propstat><D:prop>\
+             <D:displayname>{}</D:displayname>\
+             <D:getlastmodified>{}</D:getlastmodified>",
+            escape_xml(&res.href),
+            escape_xml(&res.name),
+            modified
+        )?;
+        if res.is_dir {
+            xml.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+        } else {
+            write!(
+                xml,
+                "<D:resourcetype/><D:getcontentlength>{}</D:getcontentlength>",
+                res.len
+            )?;
+        }
+        xml.push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>");
+    }
+    xml.push_str("</D:multistatus>");
+    Ok(xml)
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Method;
+    use std::path::PathBuf;
+
+    fn dav_method(name: &str) -> Method {
+        Method::from_bytes(name.as_bytes()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_propfind_multistatus() {
+        let state = Arc::new(HttpServeState {
+            path: PathBuf::from("src"),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .method(dav_method("PROPFIND"))
+            .uri("/")
+            .header("depth", "1")
+            .body(Body::empty())
+            .unwrap();
+        let res = webdav_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::MULTI_STATUS);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("<D:multistatus"));
+        assert!(body.contains("<D:href>/lib.rs</D:href>"));
+        assert!(body.contains("<D:href>/cli/</D:href>"));
+    }
+
+    #[tokio::test]
+    async fn test_mkcol_and_move() {
+        let root = tempfile::tempdir().unwrap();
+        let state = Arc::new(HttpServeState {
+            path: root.path().to_path_buf(),
+            writable: true,
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .method(dav_method("MKCOL"))
+            .uri("/new")
+            .body(Body::empty())
+            .unwrap();
+        let res = webdav_service(State(state.clone()), req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert!(root.path().join("new").is_dir());
+
+        let req = Request::builder()
+            .method(dav_method("MOVE"))
+            .uri("/new")
+            .header("destination", "http://localhost:8080/renamed")
+            .body(Body::empty())
+            .unwrap();
+        let res = webdav_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert!(root.path().join("renamed").is_dir());
+        assert!(!root.path().join("new").exists());
+    }
+
+    #[tokio::test]
+    async fn test_mkcol_read_only() {
+        let root = tempfile::tempdir().unwrap();
+        let state = Arc::new(HttpServeState {
+            path: root.path().to_path_buf(),
+            ..Default::default()
+        });
+        let req = Request::builder()
+            .method(dav_method("MKCOL"))
+            .uri("/new")
+            .body(Body::empty())
+            .unwrap();
+        let res = webdav_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(!root.path().join("new").exists());
+    }
+
+    #[tokio::test]
+    async fn test_move_and_mkcol_checks_destination() {
+        use crate::process::http_serve::{build_app, HttpServeConfig};
+        use tower::ServiceExt;
+
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("a.txt"), "a").unwrap();
+        let app = build_app(HttpServeConfig {
+            path: root.path().to_path_buf(),
+            mode: "rw".to_string(),
+            webdav: true,
+            exclude: vec![".git".to_string()],
+            protect: vec!["/private:user:pass".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let request = |method: &str, uri: &str, destination: Option<&str>| {
+            let mut req = Request::builder().method(dav_method(method)).uri(uri);
+            if let Some(destination) = destination {
+                req = req.header("destination", destination);
+            }
+            req.body(Body::empty()).unwrap()
+        };
+
+        for (dest, status) in [
+            ("http://localhost/.git/a.txt", StatusCode::FORBIDDEN),
+            ("http://localhost/private", StatusCode::UNAUTHORIZED),
+            ("http://localhost/priv%61te/a.txt", StatusCode::UNAUTHORIZED),
+        ] {
+            let req = request("MOVE", "/a.txt", Some(dest));
+            let res = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(res.status(), status, "{}", dest);
+            assert!(root.path().join("a.txt").exists());
+        }
+
+        let res = app.oneshot(request("MKCOL", "/.git", None)).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert!(!root.path().join(".git").exists());
+    }
+
+    // A root with `link` pointing at a directory outside of it.
+    #[cfg(unix)]
+    fn linked_outside() -> (tempfile::TempDir, tempfile::TempDir, Arc<HttpServeState>) {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("link")).unwrap();
+        let state = Arc::new(HttpServeState {
+            path: root.path().to_path_buf(),
+            writable: true,
+            ..Default::default()
+        });
+        (root, outside, state)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_mkcol_through_symlink() {
+        let (_root, outside, state) = linked_outside();
+        let req = Request::builder()
+            .method(dav_method("MKCOL"))
+            .uri("/link/x")
+            .body(Body::empty())
+            .unwrap();
+        let res = webdav_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert!(!outside.path().join("x").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_move_through_symlink() {
+        let (root, outside, state) = linked_outside();
+        std::fs::write(root.path().join("a.txt"), "a").unwrap();
+        let req = Request::builder()
+            .method(dav_method("MOVE"))
+            .uri("/a.txt")
+            .header("destination", "http://localhost/link/y")
+            .body(Body::empty())
+            .unwrap();
+        let res = webdav_service(State(state), req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert!(!outside.path().join("y").exists());
+        assert!(root.path().join("a.txt").exists());
+    }
+}
@@ -0,0 +1,101 @@
+use crate::read_contents;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+/// Validate a JSON document, or a CSV file as the array `csv` converts it to,
+/// against a JSON Schema. Returns each error as its instance path and message,
+/// so an empty list means the document is valid.
+pub fn process_json_validate(schema: &str, input: &str) -> Result<Vec<(String, String)>> {
+    let schema: Value = serde_json::from_slice(&read_contents(schema)?)
+        .map_err(|e| anyhow!("Invalid schema JSON: {}", e))?;
+    let validator =
+        jsonschema::validator_for(&schema).map_err(|e| anyhow!("Invalid schema: {}", e))?;
+    let instance = if input.ends_with(".csv") {
+        csv_instance(input)?
+    } else {
+        serde_json::from_slice(&read_contents(input)?)?
+    };
+    let errors = validator
+        .iter_errors(&instance)
+        .map(|e| {
+            let path = e.instance_path.to_string();
+            let path = if path.is_empty() {
+                "/".to_string()
+            } else {
+                path
+            };
+            (path, e.to_string())
+        })
+        .collect();
+    Ok(errors)
+}
+
+#[cfg(feature = "csv")]
+fn csv_instance(input: &str) -> Result<Value> {
+    Ok(Value::Array(super::csv_convert::read_csv(input)?))
+}
+
+#[cfg(not(feature = "csv"))]
+fn csv_instance(input: &str) -> Result<Value> {
+    Err(anyhow!(
+        "Can't read {}, rcli was built without the `csv` feature",
+        input
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const SCHEMA: &str = r#"{
+        "type": "array",
+        "items": {
+            "type": "object",
+            "required": ["Name", "Position"],
+            "properties": { "Name": { "type": "string", "minLength": 1 } }
+        }
+    }"#;
+
+    #[test]
+    fn test_json_validate_passing() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir.path().join("schema.json");
+        fs::write(&schema, SCHEMA).unwrap();
+        let schema = schema.to_str().unwrap();
+        let data = dir.path().join("data.json");
+        fs::write(&data, r#"[{"Name": "Buffon", "Position": "Goalkeeper"}]"#).unwrap();
+
+        let errors = process_json_validate(schema, data.to_str().unwrap()).unwrap();
+        assert!(errors.is_empty());
+        if cfg!(feature = "csv") {
+            let errors = process_json_validate(schema, "assets/juventus.csv").unwrap();
+            assert!(errors.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_json_validate_failing() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir.path().join("schema.json");
+        fs::write(&schema, SCHEMA).unwrap();
+        let data = dir.path().join("data.json");
+        fs::write(
+            &data,
+            r#"[{"Name": "Buffon", "Position": "Goalkeeper"}, {"Name": 1}]"#,
+        )
+        .unwrap();
+
+        let mut errors =
+            process_json_validate(schema.to_str().unwrap(), data.to_str().unwrap()).unwrap();
+        errors.sort();
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .all(|(path, _)| path == "/1" || path == "/1/Name"));
+        assert!(errors.iter().any(|(_, msg)| msg.contains("Position")));
+
+        fs::write(&schema, r#"{"type": "nope"}"#).unwrap();
+        assert!(process_json_validate(schema.to_str().unwrap(), data.to_str().unwrap()).is_err());
+    }
+}
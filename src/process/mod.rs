@@ -1,16 +1,70 @@
 mod b64;
+#[cfg(feature = "bench")]
+mod bench;
+#[cfg(feature = "compress")]
+mod compress;
+#[cfg(feature = "convert")]
+mod convert;
+#[cfg(feature = "csv")]
 mod csv_convert;
+#[cfg(feature = "digest")]
+mod digest;
 mod gen_pass;
+#[cfg(feature = "hex")]
+mod hex;
+#[cfg(feature = "http")]
 mod http_serve;
+#[cfg(feature = "jsonschema")]
+mod json_schema;
+#[cfg(feature = "jwt")]
 mod jwt;
+#[cfg(feature = "random")]
+mod random;
+#[cfg(feature = "text")]
 mod text;
+#[cfg(feature = "update")]
+mod update;
+#[cfg(feature = "uuid")]
+mod uuid;
 
-pub use b64::{process_decode, process_encode, URL_SAFE_ENGINE};
-pub use csv_convert::process_csv;
+pub use b64::{process_decode, process_encode, STANDARD_ENGINE, URL_SAFE_ENGINE};
+#[cfg(feature = "bench")]
+pub use bench::{process_bench, BenchReport};
+#[cfg(feature = "compress")]
+pub use compress::{
+    compression_from_extension, detect_compression, process_compress, process_decompress, MAGIC_LEN,
+};
+#[cfg(feature = "convert")]
+pub use convert::{data_format_from_extension, process_convert};
+#[cfg(feature = "csv")]
+pub use csv_convert::{process_csv, process_csv_convert};
+#[cfg(feature = "digest")]
+pub use digest::{
+    process_digest, process_digest_check, process_digest_files, process_verify_manifest,
+};
 pub use gen_pass::process_genpass;
-pub use http_serve::process_http_serve;
-pub use jwt::{process_jwt_sign_with_secret, process_jwt_verify_with_secret};
+#[cfg(feature = "hex")]
+pub use hex::{process_hex_decode, process_hex_encode};
+#[cfg(feature = "http")]
+pub use http_serve::{process_http_serve, HttpServeConfig};
+#[cfg(feature = "jsonschema")]
+pub use json_schema::process_json_validate;
+#[cfg(feature = "jwks")]
+pub use jwt::process_jwt_fetch_jwks;
+#[cfg(feature = "jwt")]
+pub use jwt::{
+    process_jwt_decode, process_jwt_genkey, process_jwt_parse_jwks, process_jwt_sign_with_secret,
+    process_jwt_verify_with_header, process_jwt_verify_with_jwks, process_jwt_verify_with_secret,
+    VerifyOptions,
+};
+#[cfg(feature = "random")]
+pub use random::process_random;
+#[cfg(feature = "text")]
 pub use text::{
-    process_text_decrypt, process_text_encrypt, process_text_generate_key, process_text_sign,
-    process_text_verify,
+    process_text_decrypt, process_text_encrypt, process_text_generate_key, process_text_keyinfo,
+    process_text_sign, process_text_verify, KeyInfo,
 };
+#[cfg(feature = "update")]
+pub use update::{process_self_update, process_update_check, UpdateCheck, GITHUB_API};
+#[cfg(feature = "uuid")]
+pub use uuid::process_gen_uuid;
@@ -0,0 +1,81 @@
+use anyhow::{anyhow, Result};
+use rand::{rngs::OsRng, seq::SliceRandom, Rng};
+
+/// One random value of the kind, drawn from the OS generator: an int or float
+/// in `[min, max]`, `len` bytes as hex, a bool, or one of the choices.
+pub fn process_random(
+    kind: &str,
+    min: f64,
+    max: f64,
+    len: usize,
+    choices: &[String],
+) -> Result<String> {
+    if (kind == "int" || kind == "float") && min > max {
+        return Err(anyhow!("--min {} is greater than --max {}", min, max));
+    }
+    let value = match kind {
+        "int" => {
+            if min.fract() != 0.0 || max.fract() != 0.0 {
+                return Err(anyhow!("int bounds must be whole numbers"));
+            }
+            OsRng.gen_range(min as i64..=max as i64).to_string()
+        }
+        "float" => OsRng.gen_range(min..=max).to_string(),
+        "bytes" => {
+            let mut buf = vec![0u8; len];
+            OsRng.fill(buf.as_mut_slice());
+            hex::encode(buf)
+        }
+        "bool" => OsRng.gen::<bool>().to_string(),
+        "choice" => choices
+            .choose(&mut OsRng)
+            .ok_or_else(|| anyhow!("--choice needs at least one value"))?
+            .clone(),
+        _ => return Err(anyhow!("Unsupported random type: {}", kind)),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_int_in_range() {
+        for _ in 0..1000 {
+            let value: i64 = process_random("int", 1.0, 100.0, 0, &[])
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!((1..=100).contains(&value));
+        }
+        assert_eq!(process_random("int", 7.0, 7.0, 0, &[]).unwrap(), "7");
+        assert!(process_random("int", 10.0, 1.0, 0, &[]).is_err());
+        assert!(process_random("int", 0.5, 1.0, 0, &[]).is_err());
+    }
+
+    #[test]
+    fn test_random_choice_from_set() {
+        let choices = ["red", "green", "blue"].map(String::from);
+        for _ in 0..100 {
+            let value = process_random("choice", 0.0, 0.0, 0, &choices).unwrap();
+            assert!(choices.contains(&value));
+        }
+        assert!(process_random("choice", 0.0, 0.0, 0, &[]).is_err());
+    }
+
+    #[test]
+    fn test_random_other_types() {
+        let value: f64 = process_random("float", -1.0, 1.0, 0, &[])
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((-1.0..=1.0).contains(&value));
+        assert_eq!(
+            process_random("bytes", 0.0, 0.0, 16, &[]).unwrap().len(),
+            32
+        );
+        let value = process_random("bool", 0.0, 0.0, 0, &[]).unwrap();
+        assert!(value == "true" || value == "false");
+    }
+}
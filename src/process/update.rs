@@ -0,0 +1,145 @@
+use crate::process_digest;
+use anyhow::{anyhow, Result};
+use self_update::{
+    backends::github::ReleaseList, get_target, self_replace, update::Release,
+    version::bump_is_greater, Download, Extract, TempDir,
+};
+use std::{env::consts::EXE_SUFFIX, fs::File};
+
+pub const GITHUB_API: &str = "https://api.github.com";
+const REPO_OWNER: &str = "okqin";
+const REPO_NAME: &str = "rcli";
+
+#[derive(Debug)]
+pub struct UpdateCheck {
+    pub current: String,
+    pub latest: String,
+    pub newer: bool,
+    release: Release,
+}
+
+/// Compare `current` with the newest release on GitHub, or the API at `api_url`.
+pub fn process_update_check(api_url: &str, current: &str) -> Result<UpdateCheck> {
+    let releases = ReleaseList::configure()
+        .repo_owner(REPO_OWNER)
+        .repo_name(REPO_NAME)
+        .with_url(api_url)
+        .build()?
+        .fetch()?;
+    // Releases come newest first.
+    let release = releases
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No rcli release found"))?;
+    Ok(UpdateCheck {
+        current: current.to_string(),
+        latest: release.version.clone(),
+        newer: bump_is_greater(current, &release.version)?,
+        release,
+    })
+}
+
+/// Download the release binary for this platform, check it against the
+/// published `<asset>.sha256` and replace the running executable with it.
+pub fn process_self_update(check: &UpdateCheck) -> Result<()> {
+    let target = get_target();
+    let assets = &check.release.assets;
+    let asset = assets
+        .iter()
+        .find(|asset| asset.name.contains(target) && !asset.name.ends_with(".sha256"))
+        .ok_or_else(|| anyhow!("Release {} has no binary for {}", check.latest, target))?;
+    let checksum = assets
+        .iter()
+        .find(|a| a.name == format!("{}.sha256", asset.name))
+        .ok_or_else(|| {
+            anyhow!(
+                "Release {} has no checksum for {}",
+                check.latest,
+                asset.name
+            )
+        })?;
+
+    let dir = TempDir::new()?;
+    let archive = dir.path().join(&asset.name);
+    let sums = dir.path().join(&checksum.name);
+    download(&asset.download_url, File::create(&archive)?)?;
+    download(&checksum.download_url, File::create(&sums)?)?;
+
+    // The checksum file is `<hex>  <name>` or just the hex digest.
+    let sums = std::fs::read_to_string(&sums)?;
+    let expected = sums.split_whitespace().next().unwrap_or_default();
+    let actual = process_digest(archive.to_str().unwrap_or_default(), "sha256")?;
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(anyhow!(
+            "Checksum mismatch for {}, not updating",
+            asset.name
+        ));
+    }
+
+    let bin_name = format!("{}{}", REPO_NAME, EXE_SUFFIX);
+    let binary = if asset.name.ends_with(".tar.gz") || asset.name.ends_with(".tgz") {
+        Extract::from_source(&archive).extract_file(dir.path(), &bin_name)?;
+        dir.path().join(&bin_name)
+    } else {
+        archive
+    };
+    self_replace::self_replace(&binary)?;
+    Ok(())
+}
+
+fn download(url: &str, dest: File) -> Result<()> {
+    Download::from_url(url)
+        .set_header(reqwest::header::ACCEPT, "application/octet-stream".parse()?)
+        .download_to(dest)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{self, BufRead, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    // Answer one request with the body, like the GitHub releases API would.
+    fn mock_releases(body: &'static str) -> (String, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = io::BufReader::new(stream);
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert!(line.starts_with("GET /repos/okqin/rcli/releases"));
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+        (url, server)
+    }
+
+    const RELEASES: &str = r#"[{"tag_name": "v0.8.0", "created_at": "2026-01-01T00:00:00Z", "assets": [
+        {"url": "http://127.0.0.1/rcli-x86_64-unknown-linux-gnu.tar.gz", "name": "rcli-x86_64-unknown-linux-gnu.tar.gz"}
+    ]}]"#;
+
+    #[test]
+    fn test_update_check() {
+        for (current, newer) in [("0.7.0", true), ("0.8.0", false), ("0.9.1", false)] {
+            let (url, server) = mock_releases(RELEASES);
+            let check = process_update_check(&url, current).unwrap();
+            server.join().unwrap();
+            assert_eq!(check.latest, "0.8.0");
+            assert_eq!(check.newer, newer, "{} against 0.8.0", current);
+        }
+    }
+}
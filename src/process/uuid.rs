@@ -0,0 +1,37 @@
+use anyhow::{anyhow, Result};
+use rand::Rng;
+use uuid::Uuid;
+
+pub fn process_gen_uuid(version: &str) -> Result<Uuid> {
+    let uuid = match version {
+        // A random node id rather than a MAC address, which v1 allows.
+        "v1" => Uuid::now_v1(&rand::thread_rng().gen()),
+        "v4" => Uuid::new_v4(),
+        "v7" => Uuid::now_v7(),
+        _ => return Err(anyhow!("Unsupported uuid version: {}", version)),
+    };
+    Ok(uuid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gen_uuid_v4() {
+        let uuid = process_gen_uuid("v4").unwrap();
+        let parsed = Uuid::parse_str(&uuid.to_string()).unwrap();
+        assert_eq!(parsed.get_version_num(), 4);
+        assert_eq!(process_gen_uuid("v1").unwrap().get_version_num(), 1);
+        assert!(process_gen_uuid("v5").is_err());
+    }
+
+    #[test]
+    fn test_gen_uuid_v7_monotonic() {
+        let uuids = (0..1000)
+            .map(|_| process_gen_uuid("v7").unwrap())
+            .collect::<Vec<_>>();
+        assert!(uuids.iter().all(|uuid| uuid.get_version_num() == 7));
+        assert!(uuids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
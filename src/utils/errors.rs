@@ -0,0 +1,98 @@
+use owo_colors::OwoColorize;
+use std::fmt;
+
+/// A failure that scripts may want to tell apart by its exit code.
+/// Anything else exits with 1.
+#[derive(Debug)]
+pub enum CliError {
+    /// Bad arguments or configuration, exits with 2 like clap's usage errors.
+    Usage(String),
+
+    /// A signature, token or checksum that doesn't verify, exits with 3.
+    Verification(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::Verification(_) => 3,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) | CliError::Verification(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// The process exit code for an error: 2 for usage, 3 for verification, else 1.
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<CliError>() {
+            return e.exit_code();
+        }
+        if let Some(e) = cause.downcast_ref::<clap::Error>() {
+            return e.exit_code();
+        }
+    }
+    1
+}
+
+pub fn render_error(err: &anyhow::Error, color: bool) -> String {
+    if color {
+        format!("{} {:?}", "Error:".red().bold(), err)
+    } else {
+        format!("Error: {:?}", err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_error() {
+        let err = anyhow::anyhow!("bad input");
+        let plain = render_error(&err, false);
+        assert!(plain.starts_with("Error: bad input"));
+        assert!(!plain.contains('\x1b'));
+        assert!(render_error(&err, true).contains('\x1b'));
+    }
+
+    #[cfg(feature = "digest")]
+    #[tokio::test]
+    async fn test_exit_code_verification_failure() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_str().unwrap();
+        let manifest = format!("sha256:{}  assets/juventus.csv\n", "0".repeat(64));
+        std::fs::write(path, manifest).unwrap();
+        let (_, result) = crate::cli::run_with_output(&["verify-file", "-m", path]).await;
+        assert_eq!(exit_code(&result.unwrap_err()), 3);
+    }
+
+    #[cfg(all(feature = "csv", feature = "digest"))]
+    #[test]
+    fn test_exit_code_missing_file() {
+        let err = crate::process_digest("assets/missing.csv", "sha256").unwrap_err();
+        assert_eq!(exit_code(&err), 1);
+        // An empty config, not the user's own.
+        let config = tempfile::NamedTempFile::new().unwrap();
+        let config = config.path().to_str().unwrap();
+        let args = [
+            "rcli",
+            "--config",
+            config,
+            "csv",
+            "-i",
+            "assets/missing.csv",
+        ];
+        let err = crate::Cli::try_parse_with_config(args).unwrap_err();
+        assert_eq!(exit_code(&err), 2);
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use std::{
+    ffi::OsString,
+    io::{self, IsTerminal},
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tracing::{debug, Subscriber};
+use tracing_subscriber::{
+    fmt::{self as log_fmt, MakeWriter},
+    prelude::*,
+    registry::LookupSpan,
+    EnvFilter, Layer,
+};
+
+// Set by the global --no-color flag.
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+pub fn disable_color() {
+    NO_COLOR.store(true, Ordering::Relaxed);
+}
+
+// Color only for a terminal, so pipes and files stay plain,
+// and never with --no-color or the NO_COLOR environment variable.
+pub fn should_color(stream: &impl IsTerminal) -> bool {
+    !NO_COLOR.load(Ordering::Relaxed)
+        && color_enabled(stream.is_terminal(), std::env::var_os("NO_COLOR"))
+}
+
+fn color_enabled(is_terminal: bool, no_color: Option<OsString>) -> bool {
+    is_terminal && no_color.is_none_or(|v| v.is_empty())
+}
+
+// Log to stderr so the output stays clean, the global subscriber can only be set once.
+pub fn init_tracing(level: &str, json: bool) -> Result<()> {
+    let filter = EnvFilter::try_new(level)?;
+    if tracing_subscriber::registry()
+        .with(filter)
+        .with(log_layer(json, io::stderr))
+        .try_init()
+        .is_err()
+    {
+        debug!("Tracing subscriber is already initialized");
+    }
+    Ok(())
+}
+
+// One JSON object per line for log aggregators, otherwise the human-readable format.
+fn log_layer<S, W>(json: bool, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = log_fmt::layer().with_writer(writer);
+    if json {
+        layer.json().boxed()
+    } else {
+        layer.with_ansi(should_color(&io::stderr())).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_log_layer_json() {
+        #[derive(Clone, Default)]
+        struct Buf(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+        impl Write for Buf {
+            fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(data)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buf = Buf::default();
+        let writer = buf.clone();
+        let subscriber =
+            tracing_subscriber::registry().with(log_layer(true, move || writer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(status = 200, path = "/index.html", "served");
+        });
+        let line = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        let event: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(event["level"], "INFO");
+        assert_eq!(event["fields"]["message"], "served");
+        assert_eq!(event["fields"]["status"], 200);
+        assert_eq!(event["fields"]["path"], "/index.html");
+        assert!(event["timestamp"].is_string());
+        assert!(event["target"].is_string());
+    }
+
+    #[test]
+    fn test_init_tracing_twice() {
+        assert!(init_tracing("debug", false).is_ok());
+        assert!(init_tracing("debug", false).is_ok());
+        assert!(init_tracing("not a level!", false).is_err());
+    }
+
+    #[test]
+    fn test_color_enabled() {
+        assert!(color_enabled(true, None));
+        assert!(color_enabled(true, Some(OsString::new())));
+        assert!(!color_enabled(true, Some(OsString::from("1"))));
+        assert!(!color_enabled(false, None));
+        assert!(!color_enabled(false, Some(OsString::from("1"))));
+    }
+}
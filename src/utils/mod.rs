@@ -0,0 +1,328 @@
+mod errors;
+mod logging;
+mod output;
+
+pub use errors::*;
+pub use logging::*;
+pub use output::*;
+
+use anyhow::Result;
+#[cfg(feature = "csv")]
+use notify::{RecursiveMode, Watcher};
+use std::{
+    fs::File,
+    io::{self, IsTerminal, Read},
+    pin::Pin,
+};
+#[cfg(feature = "csv")]
+use std::{path::Path, sync::mpsc, time::Duration};
+use tokio::io::AsyncRead;
+
+pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
+    let reader: Box<dyn Read> = if input == "-" {
+        Box::new(io::stdin())
+    } else if is_url(input) {
+        fetch(input)?
+    } else {
+        let file = File::open(input)?;
+        let len = file.metadata()?.len();
+        with_progress(Box::new(file), len)
+    };
+    Ok(reader)
+}
+
+/// Like `get_reader`, but reads without blocking the tokio runtime, for async
+/// code like the http handlers. URLs aren't supported here yet.
+pub async fn get_async_reader(input: &str) -> Result<Pin<Box<dyn AsyncRead + Send>>> {
+    let reader: Pin<Box<dyn AsyncRead + Send>> = if input == "-" {
+        Box::pin(tokio::io::stdin())
+    } else if is_url(input) {
+        return Err(anyhow::anyhow!("Can't read {} asynchronously", input));
+    } else {
+        Box::pin(tokio::fs::File::open(input).await?)
+    };
+    Ok(reader)
+}
+
+/// Counts the bytes read through it, passing the running total to `on_read`.
+pub struct CountingReader<R, F> {
+    inner: R,
+    total: u64,
+    on_read: F,
+}
+
+impl<R: Read, F: FnMut(u64)> CountingReader<R, F> {
+    pub fn new(inner: R, on_read: F) -> Self {
+        Self {
+            inner,
+            total: 0,
+            on_read,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+impl<R: Read, F: FnMut(u64)> Read for CountingReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.total += n as u64;
+        (self.on_read)(self.total);
+        Ok(n)
+    }
+}
+
+// Small inputs like key files finish before a bar would be worth drawing.
+#[cfg(feature = "progress")]
+const PROGRESS_MIN_LEN: u64 = 16 * 1024 * 1024;
+
+#[cfg(feature = "progress")]
+fn with_progress(reader: Box<dyn Read>, len: u64) -> Box<dyn Read> {
+    use indicatif::{ProgressBar, ProgressDrawTarget, ProgressFinish};
+
+    if len < PROGRESS_MIN_LEN || !io::stderr().is_terminal() {
+        return reader;
+    }
+    let bar = ProgressBar::with_draw_target(Some(len), ProgressDrawTarget::stderr())
+        .with_finish(ProgressFinish::AndClear);
+    Box::new(CountingReader::new(reader, move |total| {
+        bar.set_position(total)
+    }))
+}
+
+#[cfg(not(feature = "progress"))]
+fn with_progress(reader: Box<dyn Read>, _len: u64) -> Box<dyn Read> {
+    reader
+}
+
+pub fn is_stdin_tty() -> bool {
+    io::stdin().is_terminal()
+}
+
+/// Reading "-" from a terminal would just hang while the user wonders why,
+/// so point them at piping the data in or the option to name a file.
+pub fn check_stdin_input(input: &str, usage: &str) -> Result<()> {
+    stdin_hint(input, usage, is_stdin_tty())
+}
+
+fn stdin_hint(input: &str, usage: &str, tty: bool) -> Result<()> {
+    if input == "-" && tty {
+        return Err(CliError::Usage(format!(
+            "no input, pipe it in like `cat file | rcli ...` or pass {}",
+            usage
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+pub fn is_url(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+// Stream the response body, it's blocking as the readers are.
+#[cfg(feature = "net")]
+fn fetch(url: &str) -> Result<Box<dyn Read>> {
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| anyhow::anyhow!("Failed to fetch {}: {}", url, e))?;
+    Ok(Box::new(response.into_reader()))
+}
+
+#[cfg(not(feature = "net"))]
+fn fetch(url: &str) -> Result<Box<dyn Read>> {
+    Err(anyhow::anyhow!(
+        "Can't fetch {}, rcli was built without the `net` feature",
+        url
+    ))
+}
+
+pub fn read_contents(input: &str) -> Result<Vec<u8>> {
+    let mut reader = get_reader(input)?;
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+// Editors save in several steps, wait for them to settle before re-running.
+#[cfg(feature = "csv")]
+const WATCH_SETTLE: Duration = Duration::from_millis(100);
+
+/// Run `run` now, then again each time the file changes, until it returns false.
+#[cfg(feature = "csv")]
+pub fn watch_file(path: &Path, mut run: impl FnMut() -> bool) -> Result<()> {
+    let path = path.canonicalize()?;
+    let dir = path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Can't watch {}", path.display()))?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    // Watch the directory, as editors often replace the file rather than write to it.
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    if !run() {
+        return Ok(());
+    }
+    loop {
+        let event: notify::Event = rx.recv()??;
+        let changed = event.kind.is_modify() || event.kind.is_create();
+        if !changed || !event.paths.contains(&path) {
+            continue;
+        }
+        while rx.recv_timeout(WATCH_SETTLE).is_ok() {}
+        if !run() {
+            return Ok(());
+        }
+    }
+}
+
+/// A secret from `env`, the value of its environment variable, else typed on
+/// the terminal without echo. None when neither is available, as nobody can
+/// answer a prompt when stdin isn't a terminal. An empty variable is unset.
+#[cfg(any(feature = "jwt", feature = "text"))]
+pub fn read_secret(env: Option<String>, prompt: &str) -> Result<Option<String>> {
+    read_secret_with(env, io::stdin().is_terminal(), || {
+        rpassword::prompt_password(prompt)
+    })
+}
+
+#[cfg(any(feature = "jwt", feature = "text"))]
+fn read_secret_with(
+    env: Option<String>,
+    interactive: bool,
+    prompt: impl FnOnce() -> io::Result<String>,
+) -> Result<Option<String>> {
+    let env = env.filter(|secret| !secret.is_empty());
+    if env.is_some() || !interactive {
+        return Ok(env);
+    }
+    let secret = prompt()?;
+    Ok(Some(secret).filter(|secret| !secret.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_get_async_reader() {
+        use tokio::io::AsyncReadExt;
+
+        let mut reader = get_async_reader("Cargo.toml").await.unwrap();
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content).await.unwrap();
+        assert_eq!(content, fs::read("Cargo.toml").unwrap());
+        assert!(get_async_reader("not-found.txt").await.is_err());
+        assert!(get_async_reader("https://example.com/a.csv").await.is_err());
+    }
+
+    #[cfg(feature = "net")]
+    #[test]
+    fn test_get_reader_url() {
+        use std::{
+            io::{BufRead, Write},
+            net::TcpListener,
+            thread,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = io::BufReader::new(stream);
+            let mut line = String::new();
+            // Skip the request, up to the blank line after the headers.
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let body = "name,age\nrcli,1\n";
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        let mut content = String::new();
+        get_reader(&format!("http://{}/data.csv", addr))
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "name,age\nrcli,1\n");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com/data.csv"));
+        assert!(is_url("http://127.0.0.1:8080/"));
+        assert!(!is_url("assets/juventus.csv"));
+        assert!(!is_url("-"));
+    }
+
+    #[test]
+    fn test_counting_reader_total() {
+        let data = vec![7u8; 100_000];
+        let mut reports = Vec::new();
+        let mut reader = CountingReader::new(&data[..], |total| reports.push(total));
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).unwrap();
+        assert_eq!(reader.total(), 100_000);
+        assert_eq!(buf, data);
+        assert_eq!(reports.last(), Some(&100_000));
+        assert!(reports.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn test_stdin_hint() {
+        let err = stdin_hint("-", "--input <file>", true).unwrap_err();
+        assert!(err.to_string().ends_with("or pass --input <file>"));
+        assert_eq!(exit_code(&err), 2);
+        assert!(stdin_hint("-", "--input <file>", false).is_ok());
+        assert!(stdin_hint("Cargo.toml", "--input <file>", true).is_ok());
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_watch_file_reruns_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.csv");
+        std::fs::write(&path, "a,b\n1,2\n").unwrap();
+        let mut runs = 0;
+        watch_file(&path, || {
+            runs += 1;
+            if runs == 1 {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(Duration::from_millis(200));
+                    std::fs::write(path, "a,b\n3,4\n").unwrap();
+                });
+            }
+            runs < 2
+        })
+        .unwrap();
+        assert_eq!(runs, 2);
+    }
+
+    #[cfg(any(feature = "jwt", feature = "text"))]
+    #[test]
+    fn test_read_secret_prompt() {
+        let typed = || Ok("typed secret".to_string());
+        let secret = read_secret_with(None, true, typed).unwrap();
+        assert_eq!(secret.as_deref(), Some("typed secret"));
+        let secret = read_secret_with(Some("env".to_string()), true, typed).unwrap();
+        assert_eq!(secret.as_deref(), Some("env"));
+        assert_eq!(read_secret_with(None, false, typed).unwrap(), None);
+        let secret = read_secret_with(Some(String::new()), false, typed).unwrap();
+        assert_eq!(secret, None);
+        assert_eq!(
+            read_secret_with(None, true, || Ok(String::new())).unwrap(),
+            None
+        );
+    }
+}
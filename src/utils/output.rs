@@ -0,0 +1,377 @@
+use super::{is_stdin_tty, should_color, CliError};
+use anyhow::Result;
+use owo_colors::{OwoColorize, Style};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    fs::{self, File},
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+/// What a command produced. `main` prints it, code embedding rcli can use it
+/// as is.
+#[derive(Debug)]
+pub enum CmdOutput {
+    /// Nothing to print, the command wrote files or served until stopped.
+    None,
+    /// Text like a token or a signature, printed the same with --json.
+    Text(String),
+    /// Data that may not be text, written as is.
+    Bytes(Vec<u8>),
+    /// A generated password, with its estimated strength from 0 to 4.
+    Password { password: String, strength: u8 },
+    /// A result printed as `json` with --json, else as `lines`.
+    Json { json: Value, lines: Vec<String> },
+    /// A result that also fails the command, like checks that didn't pass.
+    /// `output` is printed before `error` is returned.
+    Failed {
+        output: Box<CmdOutput>,
+        error: anyhow::Error,
+    },
+}
+
+// Asks the user for the value of a missing argument, with --interactive.
+type Prompt = Box<dyn FnMut(&str) -> io::Result<String>>;
+
+/// What a command needs besides its options: --dry-run, --output-dir and the
+/// --interactive prompts. The default writes files as asked, without prompts.
+#[derive(Default)]
+pub struct CmdContext {
+    dry_run: bool,
+    output_dir: Option<PathBuf>,
+    prompt: Option<Prompt>,
+    // The files a dry run didn't write, and how much each would hold.
+    skipped: Vec<(PathBuf, usize)>,
+}
+
+impl CmdContext {
+    // Report the files that would be written instead of writing them.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_output_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.output_dir = dir;
+        self
+    }
+
+    // Ask on the terminal for missing required arguments, if stdin is one.
+    pub fn with_interactive(self, interactive: bool) -> Self {
+        if !interactive || !is_stdin_tty() {
+            return self;
+        }
+        self.with_prompt(prompt_input)
+    }
+
+    pub fn with_prompt(mut self, prompt: impl FnMut(&str) -> io::Result<String> + 'static) -> Self {
+        self.prompt = Some(Box::new(prompt));
+        self
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    // The value of a required argument the user left out, asked for with
+    // --interactive, otherwise the same usage error clap would give.
+    pub fn ask(&mut self, arg: &str) -> Result<String> {
+        match self.prompt.as_mut() {
+            Some(prompt) => Ok(prompt(arg)?),
+            None => Err(CliError::Usage(format!(
+                "the following required argument was not provided: {}, or pass --interactive to be asked for it",
+                arg
+            ))
+            .into()),
+        }
+    }
+
+    // Where a file named by rcli itself goes, under --output-dir if given.
+    pub fn output_path(&self, name: impl AsRef<Path>) -> Result<PathBuf> {
+        match &self.output_dir {
+            Some(dir) => {
+                if !self.dry_run {
+                    fs::create_dir_all(dir)?;
+                }
+                Ok(dir.join(name))
+            }
+            None => Ok(name.as_ref().to_path_buf()),
+        }
+    }
+
+    // Write a file a command produces, or with --dry-run note what it would hold.
+    pub fn write_file(&mut self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<()> {
+        let (path, contents) = (path.as_ref(), contents.as_ref());
+        if self.dry_run {
+            self.skip(path, contents.len());
+        } else {
+            fs::write(path, contents)?;
+        }
+        Ok(())
+    }
+
+    // Note a file a dry run didn't write, for commands writing it themselves.
+    pub fn skip(&mut self, path: impl AsRef<Path>, len: usize) {
+        self.skipped.push((path.as_ref().to_path_buf(), len));
+    }
+
+    // The files noted by `skip` since the last call.
+    pub fn take_skipped(&mut self) -> Vec<(PathBuf, usize)> {
+        std::mem::take(&mut self.skipped)
+    }
+}
+
+// Where the result of a command is printed, and whether as JSON for scripts.
+pub struct Output {
+    writer: Box<dyn Write>,
+    json: bool,
+    terminal: bool,
+    // The --output file a dry run didn't create, and how much it would hold.
+    skipped: Option<(String, usize)>,
+    // What goes on the clipboard instead of the output, with --clipboard.
+    clipboard: Option<Vec<u8>>,
+}
+
+impl Output {
+    pub fn new(output: &str, json: bool) -> Result<Self> {
+        let writer = get_writer(output)?;
+        let terminal = output == "-" && io::stdout().is_terminal();
+        Ok(Self {
+            writer,
+            json,
+            terminal,
+            skipped: None,
+            clipboard: None,
+        })
+    }
+
+    // Like `new`, but report the --output file instead of writing it.
+    pub fn dry_run(output: &str, json: bool) -> Result<Self> {
+        let mut this = Self::new("-", json)?;
+        this.terminal = output == "-" && this.terminal;
+        this.skipped = (output != "-").then(|| (output.to_string(), 0));
+        Ok(this)
+    }
+
+    // Copy the output to the clipboard rather than showing it.
+    pub fn with_clipboard(mut self, clipboard: bool) -> Self {
+        if clipboard {
+            self.clipboard = Some(Vec::new());
+            self.terminal = false;
+        }
+        self
+    }
+
+    // Print what a command produced, as JSON with --json. A failed result is
+    // printed, then its error returned.
+    pub fn print(&mut self, output: CmdOutput) -> Result<()> {
+        match output {
+            CmdOutput::None => {}
+            CmdOutput::Text(text) => writeln!(self, "{}", text)?,
+            // Binary data shouldn't be dumped on a terminal.
+            CmdOutput::Bytes(data) => {
+                if (self.json || self.terminal) && std::str::from_utf8(&data).is_err() {
+                    return Err(anyhow::anyhow!(
+                        "the output isn't text, write it to a file with --output"
+                    ));
+                }
+                self.write_all(&data)?;
+            }
+            CmdOutput::Password { password, strength } if self.json => {
+                self.write_json(&json!({
+                    "command": "genpass",
+                    "password": password,
+                    "strength": strength,
+                }))?;
+            }
+            CmdOutput::Password { password, strength } => {
+                writeln!(self, "{}", password)?;
+                let color = should_color(&io::stderr());
+                eprintln!("{}\n", render_strength(strength, color));
+            }
+            CmdOutput::Json { json, .. } if self.json => self.write_json(&json)?,
+            CmdOutput::Json { lines, .. } => {
+                for line in lines {
+                    writeln!(self, "{}", line)?;
+                }
+            }
+            CmdOutput::Failed { output, error } => {
+                self.print(*output)?;
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    // Flush the output, report the files a dry run skipped, and fill the
+    // clipboard.
+    pub fn finish(&mut self, skipped: &[(PathBuf, usize)]) -> Result<()> {
+        if let Some(buf) = self.clipboard.take() {
+            let text = String::from_utf8(buf).map_err(|_| {
+                anyhow::anyhow!("the output isn't text, it can't go on the clipboard")
+            })?;
+            to_clipboard(text.trim_end())?;
+            eprintln!("Copied to the clipboard");
+        }
+        for (path, len) in skipped {
+            writeln!(
+                self.writer,
+                "Would write {} bytes to {}",
+                len,
+                path.display()
+            )?;
+        }
+        if let Some((path, len)) = self.skipped.take() {
+            writeln!(self.writer, "Would write {} bytes to {}", len, path)?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn write_json(&mut self, value: &impl Serialize) -> Result<()> {
+        serde_json::to_writer(&mut *self, value)?;
+        writeln!(self)?;
+        Ok(())
+    }
+}
+
+// From red for a weak password to green for a strong one, scores range from 0 to 4.
+fn render_strength(score: u8, color: bool) -> String {
+    if !color {
+        return format!("Estimated strength: {}", score);
+    }
+    let style = match score {
+        0 | 1 => Style::new().red(),
+        2 => Style::new().yellow(),
+        3 => Style::new().green(),
+        _ => Style::new().bright_green().bold(),
+    };
+    format!("Estimated strength: {}", score.style(style))
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(clipboard) = &mut self.clipboard {
+            clipboard.extend_from_slice(buf);
+            return Ok(buf.len());
+        }
+        match &mut self.skipped {
+            Some((_, len)) => {
+                *len += buf.len();
+                Ok(buf.len())
+            }
+            None => self.writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(feature = "interactive")]
+fn prompt_input(name: &str) -> io::Result<String> {
+    dialoguer::Input::<String>::new()
+        .with_prompt(name)
+        .interact_text()
+        .map_err(|dialoguer::Error::IO(e)| e)
+}
+
+#[cfg(not(feature = "interactive"))]
+fn prompt_input(name: &str) -> io::Result<String> {
+    Err(io::Error::other(format!(
+        "Can't ask for {}, rcli was built without the `interactive` feature",
+        name
+    )))
+}
+
+/// Put the text on the system clipboard.
+#[cfg(feature = "clipboard")]
+pub fn to_clipboard(text: &str) -> Result<()> {
+    // On X11 the clipboard manager takes the text over when this is dropped.
+    arboard::Clipboard::new()?.set_text(text)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn to_clipboard(_text: &str) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "Can't use the clipboard, rcli was built without the `clipboard` feature"
+    ))
+}
+
+/// The text on the system clipboard.
+#[cfg(feature = "clipboard")]
+pub fn from_clipboard() -> Result<String> {
+    Ok(arboard::Clipboard::new()?.get_text()?)
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn from_clipboard() -> Result<String> {
+    Err(anyhow::anyhow!(
+        "Can't use the clipboard, rcli was built without the `clipboard` feature"
+    ))
+}
+
+pub fn get_writer(output: &str) -> Result<Box<dyn Write>> {
+    let writer: Box<dyn Write> = if output == "-" {
+        Box::new(io::stdout())
+    } else {
+        Box::new(File::create(output)?)
+    };
+    Ok(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn test_clipboard_round_trip() {
+        // Headless machines, like CI, have no clipboard to test.
+        if arboard::Clipboard::new().is_err() {
+            return;
+        }
+        to_clipboard("rcli clipboard test").unwrap();
+        assert_eq!(from_clipboard().unwrap(), "rcli clipboard test");
+    }
+
+    #[test]
+    fn test_get_writer_stdout() {
+        let mut writer = get_writer("-").unwrap();
+        writer.flush().unwrap();
+    }
+
+    #[test]
+    fn test_get_writer_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output.txt");
+        let output = path.to_str().unwrap();
+        writeln!(get_writer(output).unwrap(), "a longer first line").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "a longer first line\n"
+        );
+        // Reopening truncates, nothing of the first write is left.
+        writeln!(get_writer(output).unwrap(), "short").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "short\n");
+        assert!(get_writer(dir.path().join("missing/output.txt").to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_render_strength() {
+        assert_eq!(render_strength(1, false), "Estimated strength: 1");
+        assert!(!render_strength(4, false).contains('\x1b'));
+        assert_eq!(
+            render_strength(0, true),
+            "Estimated strength: \x1b[31m0\x1b[0m"
+        );
+        assert_ne!(render_strength(4, true), render_strength(0, true));
+    }
+}